@@ -9,7 +9,14 @@ use serde::{Deserialize, Serialize};
 use tinymist_l10n::{deserialize, TranslationMap};
 use typst::layout::PagedDocument;
 use typst_docs::{provide, Html, Resolver};
-use typst_docs_l10n::{markdown::{split_markdown, MARKDOWN_PAR_SEP}, translate::check_page, PageMdModel};
+use typst_docs_l10n::{
+    commits::CommitLog,
+    filter::PageFilter,
+    link::LinkMap,
+    markdown::{split_markdown, MARKDOWN_PAR_SEP},
+    translate::check_page,
+    PageMdModel,
+};
 
 /// Default base URL used while deriving current English source entries.
 const DEFAULT_BASE: &str = "/";
@@ -18,8 +25,6 @@ pub const DEFAULT_ISSUE_LIMIT: usize = 50;
 /// Directory name used inside top-level `{{...}}` body-file markers.
 const INCLUDED_DIR_NAME: &str = "typst-docs";
 
-
-
 /// Paths used by the translation scanner.
 #[derive(Debug, Clone)]
 pub struct RepoPaths {
@@ -29,6 +34,12 @@ pub struct RepoPaths {
     pub included_dir: PathBuf,
     /// Base URL used when collecting the current documentation source text.
     pub base: String,
+    /// Local Typst clone or JSON snapshot passed to the resolver of `typst-docs`.
+    pub commits: Option<PathBuf>,
+    /// Pages whose translations are scanned.
+    pub filter: PageFilter,
+    /// TOML file rewriting links in the documentation.
+    pub link_map: Option<PathBuf>,
 }
 
 impl RepoPaths {
@@ -47,9 +58,31 @@ impl RepoPaths {
             translations: translations.into(),
             included_dir: included_dir.into(),
             base,
+            commits: None,
+            filter: PageFilter::default(),
+            link_map: None,
         }
     }
 
+    /// Rewrites links like the `generate` command does with the same map.
+    pub fn with_link_map(mut self, link_map: Option<PathBuf>) -> Self {
+        self.link_map = link_map;
+        self
+    }
+
+    /// Only scans the translations of the pages kept by `filter`.
+    pub fn with_filter(mut self, filter: PageFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Passes the commits of a local Typst clone or a JSON snapshot to the
+    /// resolver of `typst-docs`, like the `generate` command does.
+    pub fn with_commits(mut self, commits: Option<PathBuf>) -> Self {
+        self.commits = commits;
+        self
+    }
+
     /// Returns the root directory that contains the translation files.
     fn translation_root(&self) -> PathBuf {
         self.translations
@@ -141,7 +174,17 @@ impl IssueKind {
 
 /// Scans the repository translation files and returns a structured report.
 pub fn scan_repo(paths: &RepoPaths) -> Result<ScanReport> {
-    let source_entries = collect_source_entries(&paths.base)?;
+    let commits = paths.commits.as_deref().map(CommitLog::open).transpose()?;
+    let links = match &paths.link_map {
+        Some(path) => LinkMap::load(path)?,
+        None => LinkMap::default(),
+    };
+    let resolver = ScanResolver {
+        base: &paths.base,
+        commits: commits.as_ref(),
+        links: &links,
+    };
+    let source_entries = collect_source_entries(&resolver, &paths.filter)?;
     let repo = TranslationRepo::load(paths)?;
     let issues = compare_source_entries(&source_entries, &repo, paths);
 
@@ -382,12 +425,15 @@ fn decode_disk_value(value: &str) -> Result<String> {
 }
 
 /// Collects current English source entries from the generated Typst docs model.
-fn collect_source_entries(base: &str) -> Result<Vec<SourceEntry>> {
-    let resolver = ScanResolver { base };
-    let pages = provide(&resolver)
+fn collect_source_entries(
+    resolver: &ScanResolver,
+    filter: &PageFilter,
+) -> Result<Vec<SourceEntry>> {
+    let mut pages = provide(resolver)
         .into_iter()
         .map(PageMdModel::from)
         .collect::<Vec<_>>();
+    filter.apply(&mut pages);
 
     let mut translations = vec![];
     for page in pages {
@@ -761,11 +807,22 @@ fn preview_text(text: &str) -> String {
 struct ScanResolver<'a> {
     /// Base URL used while constructing synthetic asset links.
     base: &'a str,
+    /// Commits passed to `typst-docs`, if any. Their errors are reported as
+    /// warnings.
+    commits: Option<&'a CommitLog>,
+    /// Links rewritten in the documentation.
+    links: &'a LinkMap,
 }
 
 impl Resolver for ScanResolver<'_> {
-    fn commits(&self, _from: &str, _to: &str) -> Vec<typst_docs::Commit> {
-        vec![]
+    fn commits(&self, from: &str, to: &str) -> Vec<typst_docs::Commit> {
+        let Some(log) = self.commits else {
+            return vec![];
+        };
+        log.docs_commits(from, to).unwrap_or_else(|err| {
+            eprintln!("warning: commits({from}, {to}) failed: {err:#}");
+            vec![]
+        })
     }
 
     fn example(
@@ -792,8 +849,8 @@ impl Resolver for ScanResolver<'_> {
         format!("{}assets/{filename}", self.base)
     }
 
-    fn link(&self, _link: &str) -> Option<String> {
-        None
+    fn link(&self, link: &str) -> Option<String> {
+        self.links.rewrite(link)
     }
 
     fn base(&self) -> &str {
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 
 use clap::{Parser, Subcommand, ValueEnum};
 use translate::{render_text, scan_repo, RepoPaths, DEFAULT_ISSUE_LIMIT};
+use typst_docs_l10n::filter::PageFilter;
 
 /// Scans Typst documentation translations for missing or stale entries.
 #[derive(Debug, Parser)]
@@ -36,6 +37,21 @@ struct ScanArgs {
     #[arg(long, default_value = "/")]
     base: String,
 
+    /// Local Typst clone or JSON snapshot passed to the resolver of
+    /// `typst-docs`, like the `generate` command does. The `<contributors>`
+    /// tags of the changelog are scanned as they are.
+    #[arg(long)]
+    commits: Option<PathBuf>,
+
+    /// Pages whose translations are scanned.
+    #[command(flatten)]
+    filter: PageFilter,
+
+    /// TOML file rewriting links in the documentation, as passed to
+    /// `typst-docs-l10n generate`.
+    #[arg(long)]
+    link_map: Option<PathBuf>,
+
     /// Output format for scan results.
     #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
     format: OutputFormat,
@@ -60,7 +76,10 @@ fn main() -> anyhow::Result<()> {
 
     match cli.command {
         Command::Scan(args) => {
-            let paths = RepoPaths::new(args.translations, args.included_dir, args.base);
+            let paths = RepoPaths::new(args.translations, args.included_dir, args.base)
+                .with_commits(args.commits)
+                .with_filter(args.filter)
+                .with_link_map(args.link_map);
             let limit = (args.limit != 0).then_some(args.limit);
             let report = scan_repo(&paths)?.with_issue_limit(limit);
 
@@ -0,0 +1,164 @@
+//! Collects the assets referenced by the documentation into the Typst book.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use typst::diag::StrResult;
+use typst::ecow::eco_format;
+
+use crate::cache::ExampleCache;
+use crate::resolve::write_asset;
+
+/// The assets of a Typst book. The assets generated by the `generate` command
+/// are copied next to the book, so that the book can load them.
+#[derive(Debug)]
+pub struct BookAssets {
+    /// The base URL the assets were generated with, e.g. `/docs/`.
+    base: String,
+    /// The directory the generated assets are written to.
+    assets_dir: PathBuf,
+    /// The directory the book is written to.
+    output_dir: PathBuf,
+    /// The images of the rendered examples, keyed by their source key.
+    examples: HashMap<String, Vec<String>>,
+}
+
+impl BookAssets {
+    /// The directory of the assets, relative to the book.
+    const DIR: &'static str = "assets";
+
+    /// Creates the assets of a book written to `output_dir`, given the base
+    /// URL and the assets directory passed to the `generate` command.
+    pub fn new(base: &str, assets_dir: &Path, output_dir: &Path) -> Self {
+        let mut base = base.to_owned();
+        if !base.ends_with('/') {
+            base.push('/');
+        }
+
+        // Without a cache, the examples are shown without their previews.
+        let examples = ExampleCache::load(assets_dir)
+            .map(|cache| cache.previews())
+            .unwrap_or_default();
+
+        Self {
+            base,
+            assets_dir: assets_dir.to_owned(),
+            output_dir: output_dir.to_owned(),
+            examples,
+        }
+    }
+
+    /// Copies an image into the book, returning its path relative to the
+    /// book. The image is either an asset generated by the `generate`
+    /// command, an image of the Typst development assets, which is written
+    /// into the book directly, or a file in the assets directory.
+    pub fn image(&self, src: &str) -> StrResult<String> {
+        let prefix = format!("{}assets/", self.base);
+        if let Some(filename) = src.strip_prefix(&prefix) {
+            return self.copy(filename);
+        }
+
+        if let Some(data) = typst_dev_assets::get_by_name(src) {
+            let path = Path::new(Self::DIR).join(src);
+            let target = self.output_dir.join(&path);
+            if fs::read(&target).ok().as_deref() != Some(data) {
+                write_asset(&self.output_dir.join(Self::DIR), src, data)
+                    .map_err(|err| eco_format!("failed to write {src}: {err}"))?;
+            }
+            return Ok(book_path(&path));
+        }
+
+        if self.assets_dir.join(src).is_file() {
+            return self.copy(src);
+        }
+
+        Err(eco_format!("missing image: {src}"))
+    }
+
+    /// Copies the images of a rendered example into the book, returning their
+    /// paths relative to the book, one per page.
+    pub fn example(&self, code: &str) -> StrResult<Vec<String>> {
+        let images = self
+            .examples
+            .get(&ExampleCache::code_key(code))
+            .ok_or("example was not rendered by `generate`")?;
+        images.iter().map(|image| self.copy(image)).collect()
+    }
+
+    /// Copies a file of the assets directory into the book, unless the copy
+    /// is up to date.
+    fn copy(&self, filename: &str) -> StrResult<String> {
+        let source = self.assets_dir.join(filename);
+        if !source.is_file() {
+            return Err(eco_format!("missing asset: {}", source.display()));
+        }
+
+        let path = Path::new(Self::DIR).join(filename);
+        let target = self.output_dir.join(&path);
+        if !is_up_to_date(&source, &target) {
+            let dir = target.parent().expect("parent");
+            fs::create_dir_all(dir)
+                .and_then(|_| fs::copy(&source, &target))
+                .map_err(|err| eco_format!("failed to copy {}: {err}", source.display()))?;
+        }
+
+        Ok(book_path(&path))
+    }
+}
+
+/// Whether the copy `target` of `source` has the same size and was not
+/// modified before it.
+fn is_up_to_date(source: &Path, target: &Path) -> bool {
+    let (Ok(source), Ok(target)) = (fs::metadata(source), fs::metadata(target)) else {
+        return false;
+    };
+    let modified = source
+        .modified()
+        .and_then(|source| Ok(source <= target.modified()?));
+    source.len() == target.len() && modified.unwrap_or(false)
+}
+
+/// Formats a path relative to the book as a Typst path.
+fn book_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_refreshes_changed_assets() {
+        let dir = std::env::temp_dir().join(format!("book-assets-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let assets_dir = dir.join("dist");
+        let output_dir = dir.join("book");
+        fs::create_dir_all(&assets_dir).unwrap();
+
+        let assets = BookAssets::new("/docs/", &assets_dir, &output_dir);
+        fs::write(assets_dir.join("a.png"), "old").unwrap();
+        assert_eq!(
+            assets.image("/docs/assets/a.png"),
+            Ok("assets/a.png".into())
+        );
+        fs::write(assets_dir.join("a.png"), "changed").unwrap();
+        assert_eq!(
+            assets.image("/docs/assets/a.png"),
+            Ok("assets/a.png".into())
+        );
+        assert_eq!(
+            fs::read_to_string(output_dir.join("assets/a.png")).unwrap(),
+            "changed"
+        );
+        assert_eq!(
+            assets.image("/docs/assets/b.png"),
+            Err(eco_format!(
+                "missing asset: {}",
+                assets_dir.join("b.png").display()
+            ))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+//! Caches the rendered examples of the documentation across `generate` runs.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::resolve::ExampleFormat;
+
+/// The rendered examples of the documentation, keyed by the hash of the
+/// example. An example is only rendered if it is missing from the cache, and
+/// the images of examples no longer in the documentation are removed.
+#[derive(Debug)]
+pub struct ExampleCache {
+    /// The directory the images are written to.
+    dir: PathBuf,
+    /// The examples rendered by the previous run.
+    previous: BTreeMap<String, ManifestEntry>,
+    /// The examples used by this run.
+    current: Mutex<BTreeMap<String, ManifestEntry>>,
+}
+
+/// An example in the manifest of the cache.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The file names of the images, one per page and format, relative to
+    /// the assets directory.
+    pub files: Vec<String>,
+    /// The format the images were rendered in.
+    pub format: ExampleFormat,
+    /// The resolution of the raster images, if any.
+    pub dpi: Option<f32>,
+    /// The key of the source shown next to the images, if any, see
+    /// [`ExampleCache::code_key`].
+    pub code: Option<String>,
+}
+
+impl ExampleCache {
+    /// The file name of the manifest, relative to the assets directory.
+    pub const MANIFEST: &'static str = "examples.json";
+
+    /// Loads the cache of the images in `dir`. A missing manifest is an empty
+    /// cache, and so is a manifest of an older format, whose examples are
+    /// rendered again.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let previous = match fs::read_to_string(dir.join(Self::MANIFEST)) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            dir: dir.to_owned(),
+            previous,
+            current: Mutex::default(),
+        })
+    }
+
+    /// Gets the key of an example.
+    pub fn key(hash: u128) -> String {
+        format!("{hash:x}")
+    }
+
+    /// Gets the key of the source of an example as shown in the documentation,
+    /// which finds the images of the example in the books.
+    pub fn code_key(code: &str) -> String {
+        // FNV-1a, which is stable across builds, unlike the hasher of `std`.
+        let hash = code
+            .trim_matches('\n')
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3)
+            });
+        format!("{hash:016x}")
+    }
+
+    /// Looks up an example rendered in `format` at `dpi`, by this run or the
+    /// previous one, marking it as used with the source key `code`. Returns
+    /// the file names of its images.
+    pub fn get(
+        &self,
+        hash: u128,
+        format: ExampleFormat,
+        dpi: Option<f32>,
+        code: Option<String>,
+    ) -> Option<Vec<String>> {
+        let key = Self::key(hash);
+        let mut current = self.current.lock().unwrap();
+        if let Some(entry) = current.get(&key) {
+            return Some(entry.files.clone());
+        }
+
+        let entry = self.previous.get(&key)?;
+        if entry.format != format
+            || entry.dpi != dpi
+            || !entry.files.iter().all(|file| self.dir.join(file).is_file())
+        {
+            return None;
+        }
+
+        let entry = ManifestEntry {
+            code,
+            ..entry.clone()
+        };
+        let files = entry.files.clone();
+        current.insert(key, entry);
+        Some(files)
+    }
+
+    /// Gets the images of the previously rendered examples, keyed by their
+    /// source key. The preferred image of each page is listed.
+    pub fn previews(&self) -> HashMap<String, Vec<String>> {
+        self.previous
+            .values()
+            .filter_map(|entry| {
+                let per_page = entry.format.extensions().len();
+                let files = entry.files.chunks(per_page).map(|names| names[0].clone());
+                Some((entry.code.clone()?, files.collect()))
+            })
+            .collect()
+    }
+
+    /// Records a rendered example.
+    pub fn insert(&self, hash: u128, entry: ManifestEntry) {
+        self.current.lock().unwrap().insert(Self::key(hash), entry);
+    }
+
+    /// Writes the manifest of the examples used by this run and removes the
+    /// images of the other examples. Returns the removed files.
+    pub fn finish(self) -> io::Result<Vec<PathBuf>> {
+        let current = self.current.into_inner().unwrap();
+
+        // The images of examples are the listed ones, and those named after
+        // a hash, e.g. rendered before the manifest existed. Short names are
+        // left alone, as they may be images of the documentation.
+        let used: HashSet<&str> = current
+            .values()
+            .flat_map(|entry| &entry.files)
+            .map(String::as_str)
+            .collect();
+        let mut orphans: Vec<PathBuf> = self
+            .previous
+            .values()
+            .flat_map(|entry| &entry.files)
+            .filter(|file| !used.contains(file.as_str()))
+            .map(|file| self.dir.join(file))
+            .collect();
+        if self.dir.is_dir() {
+            for entry in fs::read_dir(&self.dir)? {
+                let path = entry?.path();
+                let is_orphan = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| is_example_file(name) && !used.contains(name));
+                if is_orphan && !orphans.contains(&path) {
+                    orphans.push(path);
+                }
+            }
+        }
+
+        let mut removed = vec![];
+        for path in orphans {
+            match fs::remove_file(&path) {
+                Ok(()) => removed.push(path),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string_pretty(&current)?;
+        fs::write(self.dir.join(Self::MANIFEST), json)?;
+        Ok(removed)
+    }
+}
+
+/// Whether a file is named like an image of an example, e.g. `{hash}.png` or
+/// `{hash}-2.svg` for the second page.
+fn is_example_file(name: &str) -> bool {
+    let Some((stem, ext)) = name.rsplit_once('.') else {
+        return false;
+    };
+    let hash = match stem.split_once('-') {
+        Some((hash, page)) if !page.is_empty() && page.bytes().all(|b| b.is_ascii_digit()) => hash,
+        Some(_) => return false,
+        None => stem,
+    };
+    matches!(ext, "png" | "svg") && hash.len() >= 16 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The hashes of the examples.
+    const A: u128 = 0xaaaa_aaaa_aaaa_aaaa;
+    const B: u128 = 0xbbbb_bbbb_bbbb_bbbb;
+    const C: u128 = 0xcccc_cccc_cccc_cccc;
+
+    /// The resolution of the images in the tests.
+    const DPI: Option<f32> = Some(144.0);
+
+    /// Makes an entry for the example with the given hash.
+    fn entry(hash: u128) -> ManifestEntry {
+        ManifestEntry {
+            files: vec![file(hash)],
+            format: ExampleFormat::Png,
+            dpi: DPI,
+            code: None,
+        }
+    }
+
+    /// Gets the file name of the image of an example.
+    fn file(hash: u128) -> String {
+        format!("{hash:x}.png")
+    }
+
+    #[test]
+    fn test_is_example_file() {
+        assert!(is_example_file("aaaaaaaaaaaaaaaa.png"));
+        assert!(is_example_file("aaaaaaaaaaaaaaaa-12.svg"));
+        assert!(!is_example_file("aaaaaaaaaaaaaaaa-x.svg"));
+        assert!(!is_example_file("aaaaaaaaaaaaaaaa.json"));
+        assert!(!is_example_file("bad.png"));
+    }
+
+    #[test]
+    fn test_code_key() {
+        assert_eq!(ExampleCache::code_key(""), "cbf29ce484222325");
+        assert_eq!(
+            ExampleCache::code_key("\n= Hi\n"),
+            ExampleCache::code_key("= Hi")
+        );
+        assert_ne!(
+            ExampleCache::code_key("= Hi"),
+            ExampleCache::code_key("= Ho")
+        );
+    }
+
+    #[test]
+    fn test_example_cache() {
+        let dir = std::env::temp_dir().join(format!("example-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let cache = ExampleCache::load(&dir).unwrap();
+        assert_eq!(cache.get(A, ExampleFormat::Png, DPI, None), None);
+        fs::create_dir_all(&dir).unwrap();
+        for hash in [A, B, C] {
+            fs::write(dir.join(file(hash)), "png").unwrap();
+        }
+        fs::write(dir.join(format!("{C:x}-2.svg")), "svg").unwrap();
+        fs::write(dir.join("bad.png"), "png").unwrap();
+        cache.insert(A, entry(A));
+        cache.insert(B, entry(B));
+        assert_eq!(
+            cache.get(A, ExampleFormat::Png, DPI, None),
+            Some(vec![file(A)])
+        );
+        let mut removed = cache.finish().unwrap();
+        removed.sort();
+        assert_eq!(
+            removed,
+            vec![dir.join(format!("{C:x}-2.svg")), dir.join(file(C))]
+        );
+
+        // Only `A` is still used, so `B` is removed.
+        let cache = ExampleCache::load(&dir).unwrap();
+        assert_eq!(cache.get(A, ExampleFormat::Png, Some(72.0), None), None);
+        assert_eq!(cache.get(A, ExampleFormat::Svg, None, None), None);
+        assert_eq!(
+            cache.get(A, ExampleFormat::Png, DPI, None),
+            Some(vec![file(A)])
+        );
+        assert_eq!(cache.finish().unwrap(), vec![dir.join(file(B))]);
+        assert!(dir.join(file(A)).is_file());
+        assert!(dir.join("bad.png").is_file());
+
+        let cache = ExampleCache::load(&dir).unwrap();
+        assert_eq!(cache.previous.len(), 1);
+        assert_eq!(cache.previews(), HashMap::new());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+//! Reads the commits of the Typst repository without network access, so that
+//! the changelog can list the contributors of each release.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+/// The maintainers, who are not thanked in the changelog.
+const STAFF: &[&str] = &["laurmaedje", "reknih"];
+
+/// The bots, which are not thanked in the changelog.
+const BOTS: &[&str] = &["dependabot[bot]"];
+
+/// The commits of the Typst repository, read from a local clone or from a
+/// JSON snapshot.
+#[derive(Debug)]
+pub enum CommitLog {
+    /// A local clone of the repository. Authors are known by their GitHub
+    /// accounts if they commit with a GitHub no-reply address or are listed
+    /// in the author map.
+    Git(PathBuf, AuthorMap),
+    /// A snapshot mapping ranges like `v0.12.0..v0.13.0` to the commits in
+    /// them, in the format of the GitHub API.
+    Snapshot(BTreeMap<String, Vec<CommitEntry>>),
+}
+
+/// Maps the authors of a local clone to their GitHub logins, loaded from a
+/// TOML file like:
+///
+/// ```toml
+/// [authors]
+/// "Jane Doe" = "jane"
+/// "jane@example.com" = "jane"
+/// ```
+///
+/// An author is looked up by their email address first, then by their name,
+/// both as mapped by the `.mailmap` of the clone.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct AuthorMap {
+    /// The logins of the author names and email addresses.
+    #[serde(default)]
+    authors: BTreeMap<String, String>,
+}
+
+impl AuthorMap {
+    /// Loads an author map from a TOML file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read the author map {}", path.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("failed to parse the author map {}", path.display()))
+    }
+
+    /// Gets the GitHub account of a commit author.
+    fn author(&self, name: &str, email: &str) -> Option<CommitAuthor> {
+        github_author(email).or_else(|| {
+            let login = self.authors.get(email).or_else(|| self.authors.get(name))?;
+            Some(CommitAuthor {
+                login: login.clone(),
+                avatar_url: format!("https://github.com/{login}.png?v=4"),
+                html_url: format!("https://github.com/{login}"),
+            })
+        })
+    }
+}
+
+/// A commit of the Typst repository.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitEntry {
+    /// The GitHub account of the author, if known.
+    pub author: Option<CommitAuthor>,
+    /// The name of the author in a local clone, if their GitHub account is
+    /// not known.
+    #[serde(skip)]
+    pub name: Option<String>,
+}
+
+/// The contributors of a release.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contributors {
+    /// The HTML list of the contributors.
+    pub html: String,
+    /// The names of the authors whose GitHub accounts are not known, who are
+    /// left out of the list.
+    pub unknown: Vec<String>,
+}
+
+/// The GitHub account of the author of a commit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitAuthor {
+    /// The login of the account.
+    pub login: String,
+    /// The URL of the avatar of the account.
+    pub avatar_url: String,
+    /// The URL of the profile of the account.
+    pub html_url: String,
+}
+
+impl CommitLog {
+    /// Opens a commit log. A directory is read as a local clone, and a file as
+    /// a JSON snapshot.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        if path.is_dir() {
+            return Ok(Self::Git(path.to_owned(), AuthorMap::default()));
+        }
+
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("failed to read the commits in {}", path.display()))?;
+        let snapshot = serde_json::from_str(&json)
+            .with_context(|| format!("failed to parse the commits in {}", path.display()))?;
+        Ok(Self::Snapshot(snapshot))
+    }
+
+    /// Looks up the authors of a local clone in `authors`. A snapshot already
+    /// knows the GitHub accounts of the authors.
+    pub fn with_authors(self, authors: AuthorMap) -> Self {
+        match self {
+            Self::Git(repo, _) => Self::Git(repo, authors),
+            snapshot => snapshot,
+        }
+    }
+
+    /// Gets the commits reachable from `to` but not from `from`.
+    pub fn commits(&self, from: &str, to: &str) -> anyhow::Result<Vec<CommitEntry>> {
+        match self {
+            Self::Git(repo, authors) => git_commits(repo, authors, from, to),
+            Self::Snapshot(snapshot) => match snapshot.get(&format!("{from}..{to}")) {
+                Some(commits) => Ok(commits.clone()),
+                None => bail!("the commits between {from} and {to} are not in the snapshot"),
+            },
+        }
+    }
+
+    /// Gets the commits as expected by the resolver of `typst-docs`.
+    pub fn docs_commits(&self, from: &str, to: &str) -> anyhow::Result<Vec<typst_docs::Commit>> {
+        let commits = self.commits(from, to)?;
+        Ok(serde_json::from_value(serde_json::to_value(commits)?)?)
+    }
+}
+
+/// Reads the commits of a local clone with `git log`, looking up their
+/// authors in `authors` by the names and email addresses the mailmap of the
+/// clone maps them to.
+fn git_commits(
+    repo: &Path,
+    authors: &AuthorMap,
+    from: &str,
+    to: &str,
+) -> anyhow::Result<Vec<CommitEntry>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["log", "--format=%aN%x00%aE", &format!("{from}..{to}")])
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git log {from}..{to} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            let (name, email) = line.split_once('\0').unwrap_or((line, ""));
+            let author = authors.author(name, email);
+            CommitEntry {
+                name: author.is_none().then(|| name.to_owned()),
+                author,
+            }
+        })
+        .collect())
+}
+
+/// Gets the GitHub account of a commit author from their no-reply address,
+/// e.g. `12345+login@users.noreply.github.com`.
+fn github_author(email: &str) -> Option<CommitAuthor> {
+    let user = email.strip_suffix("@users.noreply.github.com")?;
+    let (id, login) = match user.split_once('+') {
+        Some((id, login)) => (Some(id), login),
+        None => (None, user),
+    };
+    let avatar_url = match id {
+        Some(id) => format!("https://avatars.githubusercontent.com/u/{id}?v=4"),
+        None => format!("https://github.com/{login}.png?v=4"),
+    };
+
+    Some(CommitAuthor {
+        login: login.to_owned(),
+        avatar_url,
+        html_url: format!("https://github.com/{login}"),
+    })
+}
+
+/// Renders the list of the contributors of some commits, by decreasing number
+/// of commits, like the Typst documentation does.
+pub fn contributors(commits: &[CommitEntry]) -> Contributors {
+    let mut unknown: Vec<_> = commits
+        .iter()
+        .filter_map(|commit| commit.name.clone())
+        .collect();
+    unknown.sort();
+    unknown.dedup();
+    Contributors {
+        html: contributors_html(commits),
+        unknown,
+    }
+}
+
+/// Renders the HTML list of the contributors of some commits.
+fn contributors_html(commits: &[CommitEntry]) -> String {
+    let mut counts = HashMap::<&str, (&CommitAuthor, usize)>::new();
+    for author in commits.iter().filter_map(|commit| commit.author.as_ref()) {
+        counts.entry(&author.login).or_insert((author, 0)).1 += 1;
+    }
+
+    let mut authors: Vec<_> = counts
+        .into_values()
+        .filter(|(author, _)| {
+            !STAFF.contains(&author.login.as_str()) && !BOTS.contains(&author.login.as_str())
+        })
+        .collect();
+    if authors.is_empty() {
+        return String::new();
+    }
+    authors.sort_by_key(|(author, count)| (Reverse(*count), author.login.as_str()));
+
+    let mut html = "Thanks to everyone who contributed to this release!\n\n".to_owned();
+    html.push_str(r#"<ul class="contribs">"#);
+    for (author, count) in authors {
+        let login = escape_attr(&author.login);
+        let url = escape_attr(&author.html_url);
+        let avatar = escape_attr(&author.avatar_url.replace("?v=", "?s=64&v="));
+        let s = if count > 1 { "s" } else { "" };
+        html.push_str(&format!(
+            r#"<li><a href="{url}" target="_blank"><img width="64" height="64" src="{avatar}" alt="GitHub avatar of {login}" title="@{login} made {count} contribution{s}" crossorigin="anonymous"></a></li>"#,
+        ));
+    }
+    html.push_str("</ul>");
+    html
+}
+
+/// Escapes a value of a quoted HTML attribute.
+fn escape_attr(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_author() {
+        let author = github_author("12345+octo@users.noreply.github.com").unwrap();
+        assert_eq!(author.login, "octo");
+        assert_eq!(
+            author.avatar_url,
+            "https://avatars.githubusercontent.com/u/12345?v=4"
+        );
+        assert_eq!(author.html_url, "https://github.com/octo");
+        assert_eq!(github_author("octo@example.com"), None);
+    }
+
+    #[test]
+    fn test_author_map() {
+        let map: AuthorMap = toml::from_str(
+            r#"
+            [authors]
+            "Jane Doe" = "jane"
+            "octo@example.com" = "octo"
+            "#,
+        )
+        .unwrap();
+        let login = |name, email| map.author(name, email).map(|author| author.login);
+        assert_eq!(
+            login("Octo Cat", "octo@example.com").as_deref(),
+            Some("octo")
+        );
+        assert_eq!(
+            login("Jane Doe", "jane@example.com").as_deref(),
+            Some("jane")
+        );
+        assert_eq!(
+            login("Bob", "1+bob@users.noreply.github.com").as_deref(),
+            Some("bob")
+        );
+        assert_eq!(login("Bob", "bob@example.com"), None);
+
+        let commits = [
+            CommitEntry {
+                author: map.author("Jane Doe", "jane@example.com"),
+                name: None,
+            },
+            CommitEntry {
+                author: None,
+                name: Some("Bob".into()),
+            },
+            CommitEntry {
+                author: None,
+                name: Some("Bob".into()),
+            },
+        ];
+        assert_eq!(contributors(&commits).unknown, vec!["Bob"]);
+    }
+
+    #[test]
+    fn test_contributors() {
+        let snapshot = r#"{"v0.1.0..v0.2.0": [
+            {"author": {"login": "octo", "avatar_url": "https://a/1?v=4", "html_url": "https://github.com/octo"}},
+            {"author": {"login": "laurmaedje", "avatar_url": "https://a/2?v=4", "html_url": "https://github.com/laurmaedje"}},
+            {"author": {"login": "octo", "avatar_url": "https://a/1?v=4", "html_url": "https://github.com/octo"}},
+            {"author": null}
+        ]}"#;
+        let log = CommitLog::Snapshot(serde_json::from_str(snapshot).unwrap());
+
+        let commits = log.commits("v0.1.0", "v0.2.0").unwrap();
+        insta::assert_snapshot!(contributors(&commits).html, @r#"
+        Thanks to everyone who contributed to this release!
+
+        <ul class="contribs"><li><a href="https://github.com/octo" target="_blank"><img width="64" height="64" src="https://a/1?s=64&amp;v=4" alt="GitHub avatar of octo" title="@octo made 2 contributions" crossorigin="anonymous"></a></li></ul>
+        "#);
+        assert_eq!(
+            log.commits("v0.2.0", "v0.3.0").unwrap_err().to_string(),
+            "the commits between v0.2.0 and v0.3.0 are not in the snapshot"
+        );
+        assert_eq!(log.docs_commits("v0.1.0", "v0.2.0").unwrap().len(), 4);
+
+        let author = CommitAuthor {
+            login: "a&b".into(),
+            avatar_url: "https://a/3".into(),
+            html_url: r#"https://github.com/a"b"#.into(),
+        };
+        let html = contributors(&[CommitEntry {
+            author: Some(author),
+            name: None,
+        }])
+        .html;
+        assert!(html.contains(r#"<a href="https://github.com/a&quot;b" target="_blank">"#));
+        assert!(html.contains(r#"alt="GitHub avatar of a&amp;b""#));
+    }
+}
@@ -2,12 +2,21 @@
 
 use pulldown_cmark::{self as md, LinkType};
 use typst::diag::StrResult;
+use typst::ecow::eco_format;
 
+use std::collections::HashMap;
+use std::fmt;
 use std::io;
+use std::iter::Peekable;
+use std::ops::Range;
 
 use md::escape::StrWrite;
 use md::{CodeBlockKind, Event, Tag};
 
+use crate::commits::Contributors;
+use crate::diag::Problem;
+use crate::html::{resolve_html, HtmlLowering};
+
 /// Converts a Markdown document to Typst.
 pub fn push_typst<'a, I>(s: &mut String, iter: I)
 where
@@ -16,21 +25,101 @@ where
     TypstWriter::new(iter, s).run().unwrap();
 }
 
+/// Resolves the destinations of links and images in documents.
+pub trait LinkResolver {
+    /// Resolves a link destination to the name of a Typst label. Returns
+    /// `None` to keep the destination as a URL.
+    fn resolve_link(&self, link: &str) -> StrResult<Option<String>>;
+
+    /// Rewrites a link destination to another URL, e.g. to a localized mirror.
+    /// Rewritten links are not resolved to labels.
+    fn rewrite_link(&self, _link: &str) -> Option<String> {
+        None
+    }
+
+    /// Resolves the source of an image to a path loadable by the Typst book.
+    /// Returns `None` to keep the source as it is.
+    fn resolve_image(&self, _src: &str) -> StrResult<Option<String>> {
+        Ok(None)
+    }
+
+    /// Resolves the ID of the `index`-th of the `count` headings of a
+    /// document to the name of a Typst label. Returns `None` to leave the
+    /// heading unlabelled.
+    fn resolve_heading(
+        &self,
+        _index: usize,
+        _count: usize,
+        _id: &str,
+    ) -> StrResult<Option<String>> {
+        Ok(None)
+    }
+
+    /// Resolves the source of an example, as shown in the documentation, to
+    /// the paths of the images of its pages. Returns `None` to show the
+    /// source without a preview.
+    fn resolve_example(&self, _code: &str) -> StrResult<Option<Vec<String>>> {
+        Ok(None)
+    }
+
+    /// Resolves the contributors of the commits between the tags `from` and
+    /// `to`. Returns `None` to leave the `<contributors>` tag out.
+    fn resolve_contributors(&self, _from: &str, _to: &str) -> StrResult<Option<Contributors>> {
+        Ok(None)
+    }
+
+    /// Gets the depth of the Typst heading for a Markdown heading of the
+    /// given level.
+    fn heading_depth(&self, level: usize) -> usize {
+        level
+    }
+}
+
+/// A link resolver that keeps all destinations as URLs.
+impl LinkResolver for () {
+    fn resolve_link(&self, _link: &str) -> StrResult<Option<String>> {
+        Ok(None)
+    }
+}
+
 /// Converts a Markdown document to Typst.
 pub fn md_to_typst(text: &str) -> io::Result<String> {
+    convert(text, &(), &mut vec![])
+}
+
+/// Converts a Markdown document to Typst, resolving links with `links` and
+/// collecting the unresolvable references and unknown HTML tags into
+/// `problems`.
+pub fn md_to_typst_with(
+    text: &str,
+    links: &dyn LinkResolver,
+    problems: &mut Vec<Problem>,
+) -> io::Result<String> {
+    convert(text, links, problems)
+}
+
+/// Converts a Markdown document to Typst, collecting problems.
+fn convert(
+    text: &str,
+    links: &dyn LinkResolver,
+    problems: &mut Vec<Problem>,
+) -> io::Result<String> {
     let options = md::Options::ENABLE_TABLES
         | md::Options::ENABLE_FOOTNOTES
         | md::Options::ENABLE_STRIKETHROUGH
+        | md::Options::ENABLE_TASKLISTS
         | md::Options::ENABLE_HEADING_ATTRIBUTES;
 
     // Convert `[foo]` to `[foo]($foo)`.
+    let mut broken_links = vec![];
     let mut link = |broken: md::BrokenLink| {
-        assert_eq!(
-            broken.link_type,
-            md::LinkType::Shortcut,
-            "unsupported link type: {:?}",
-            broken.link_type,
-        );
+        if broken.link_type != md::LinkType::Shortcut {
+            broken_links.push(Problem::error(
+                broken.span,
+                eco_format!("unsupported link type: {:?}", broken.link_type),
+            ));
+            return None;
+        }
 
         Some((
             format!("${}", broken.reference.trim_matches('`')).into(),
@@ -38,39 +127,282 @@ pub fn md_to_typst(text: &str) -> io::Result<String> {
         ))
     };
 
-    let mut events =
-        md::Parser::new_with_broken_link_callback(text, options, Some(&mut link)).peekable();
-    let mut handler = Handler::new();
+    let mut events = md::Parser::new_with_broken_link_callback(text, options, Some(&mut link))
+        .into_offset_iter();
+    let mut handler = Handler::new(links);
 
     let iter = std::iter::from_fn(|| loop {
-        let mut event = events.next()?;
-        if handler.handle(&mut event) {
+        let (mut event, span) = events.next()?;
+        if handler.handle(&mut event, span) {
             return Some(event);
         }
     });
 
-    let mut result = String::new();
-    let writer = TypstWriter::new(iter, &mut result);
-    writer.run()?;
+    let events = iter.collect::<Vec<_>>();
+    problems.append(&mut broken_links);
+    for (name, span) in &handler.footnotes {
+        let defined = events.iter().any(|event| {
+            matches!(event, Event::Start(Tag::FootnoteDefinition(def)) if def.as_ref() == name)
+        });
+        if !defined {
+            problems.push(Problem::error(
+                span.clone(),
+                eco_format!("missing footnote definition: [^{name}]"),
+            ));
+        }
+    }
 
+    let headings = heading_labels(&events, &handler.headings, links, problems);
+    let examples = example_previews(&events, &handler.examples, links, problems);
+    problems.append(&mut handler.problems);
+    problems.extend(handler.html.finish(&mut String::new())?);
+
+    // Footnotes may be defined anywhere in the document, so they are written
+    // before the text referencing them, the same way as the text.
+    let (text, definitions) = split_footnotes(events, headings, examples);
+    let mut footnotes = HashMap::new();
+    for (name, definition) in definitions {
+        let mut body = String::new();
+        definition.write(&mut body, links, HashMap::new())?;
+        footnotes.insert(name, body.trim().to_owned());
+    }
+
+    let mut result = String::new();
+    text.write(&mut result, links, footnotes)?;
     Ok(result)
 }
 
+/// Resolves the labels of the headings of a document, in order. A heading is
+/// identified by its explicit ID, e.g. `## Heading {#custom-id}`, or by its
+/// text in kebab case. First-level headings are titles and are not labelled.
+fn heading_labels(
+    events: &[Event],
+    spans: &[Range<usize>],
+    links: &dyn LinkResolver,
+    problems: &mut Vec<Problem>,
+) -> Vec<Option<String>> {
+    let count = events
+        .iter()
+        .filter(|event| {
+            matches!(event, Event::Start(Tag::Heading(level, ..)) if *level != md::HeadingLevel::H1)
+        })
+        .count();
+    let mut labels: Vec<Option<String>> = vec![];
+    let mut index = 0;
+    let mut heading = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading(level, id, _)) => {
+                heading = Some((*level, *id, String::new()))
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, body)) = &mut heading {
+                    body.push_str(text);
+                }
+            }
+            Event::End(Tag::Heading(..)) => {
+                let Some((level, id, body)) = heading.take() else {
+                    continue;
+                };
+                if level == md::HeadingLevel::H1 {
+                    labels.push(None);
+                    continue;
+                }
+
+                let id = id.map_or_else(|| heading_id(&body), str::to_owned);
+                match links.resolve_heading(index, count, &id) {
+                    Ok(label) => labels.push(label),
+                    Err(err) => {
+                        let span = spans.get(labels.len()).cloned().unwrap_or_default();
+                        let message = eco_format!("unresolvable reference: #{id} ({err})");
+                        problems.push(Problem::error(span, message));
+                        labels.push(None);
+                    }
+                }
+                index += 1;
+            }
+            _ => {}
+        }
+    }
+    labels
+}
+
+/// Resolves the previews of the examples of a document, in order.
+fn example_previews(
+    events: &[Event],
+    spans: &[Range<usize>],
+    links: &dyn LinkResolver,
+    problems: &mut Vec<Problem>,
+) -> Vec<Option<Vec<String>>> {
+    let mut previews = vec![];
+    let mut example = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) if is_example(lang) => {
+                example = Some(String::new());
+            }
+            Event::Text(text) => {
+                if let Some(code) = &mut example {
+                    code.push_str(text);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                let Some(code) = example.take() else {
+                    continue;
+                };
+
+                match links.resolve_example(&example_source(&code)) {
+                    Ok(preview) => previews.push(preview),
+                    Err(err) => {
+                        let span = spans.get(previews.len()).cloned();
+                        problems.push(Problem::warning(span, err));
+                        previews.push(None);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    previews
+}
+
+/// Whether the language of a fenced code block marks an example, whose source
+/// is shown next to its preview, e.g. `example` or `example:single`.
+fn is_example(lang: &str) -> bool {
+    lang.split(':').next() == Some("example")
+}
+
+/// Gets the source of an example as shown in the documentation. Lines starting
+/// with `>>>` are only compiled, and lines starting with `<<<` are only shown.
+fn example_source(code: &str) -> String {
+    let mut source = String::with_capacity(code.len());
+    for line in code.lines() {
+        if line.starts_with(">>>") {
+            continue;
+        }
+        source.push_str(line.strip_prefix("<<< ").unwrap_or(line));
+        source.push('\n');
+    }
+    source
+}
+
+/// Makes the default ID of a heading, i.e. its text in kebab case.
+fn heading_id(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// A part of a Markdown document written on its own, i.e. its text or the
+/// definition of a footnote.
+#[derive(Default)]
+struct Section<'a> {
+    /// The events of the section.
+    events: Vec<Event<'a>>,
+    /// The labels of the headings of the section, in order.
+    headings: Vec<Option<String>>,
+    /// The images of the previews of the examples of the section, in order.
+    examples: Vec<Option<Vec<String>>>,
+}
+
+impl Section<'_> {
+    /// Writes the section as Typst, resolving links with `links` and writing
+    /// the referenced `footnotes`.
+    fn write(
+        self,
+        out: &mut String,
+        links: &dyn LinkResolver,
+        footnotes: HashMap<String, String>,
+    ) -> io::Result<()> {
+        let mut writer = TypstWriter::new(self.events.into_iter(), out);
+        writer.footnotes = footnotes;
+        writer.headings = self.headings.into_iter();
+        writer.examples = self.examples.into_iter();
+        writer.links = links;
+        writer.html = HtmlLowering::nested(links.heading_depth(1) - 1);
+        writer.run()
+    }
+}
+
+/// The definitions of footnotes, paired with the names of footnotes.
+type FootnoteDefinitions<'a> = Vec<(String, Section<'a>)>;
+
+/// Splits the footnote definitions out of the events of a document, with the
+/// labels of the `headings` and the previews of the `examples` of the document
+/// found in them. Returns the remaining text and each definition.
+fn split_footnotes<'a>(
+    events: Vec<Event<'a>>,
+    headings: Vec<Option<String>>,
+    examples: Vec<Option<Vec<String>>>,
+) -> (Section<'a>, FootnoteDefinitions<'a>) {
+    let mut headings = headings.into_iter();
+    let mut examples = examples.into_iter();
+    let mut text = Section::default();
+    let mut definitions: FootnoteDefinitions<'a> = vec![];
+    let mut in_definition = false;
+    for event in events {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(name)) => {
+                definitions.push((name.to_string(), Section::default()));
+                in_definition = true;
+                continue;
+            }
+            Event::End(Tag::FootnoteDefinition(_)) => {
+                in_definition = false;
+                continue;
+            }
+            _ => {}
+        }
+
+        let section = match definitions.last_mut() {
+            Some((_, definition)) if in_definition => definition,
+            _ => &mut text,
+        };
+        match &event {
+            Event::Start(Tag::Heading(..)) => section.headings.extend(headings.next()),
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) if is_example(lang) => {
+                section.examples.extend(examples.next())
+            }
+            _ => {}
+        }
+        section.events.push(event);
+    }
+    (text, definitions)
+}
+
 /// A writer that converts Markdown to Typst.
-struct TypstWriter<I, W> {
+struct TypstWriter<'l, I: Iterator, W> {
     /// Iterator supplying events.
-    iter: I,
+    iter: Peekable<I>,
     /// Writer to write to.
-    writer: W,
+    writer: LineWriter<W>,
     /// Whether or not the last write wrote a newline.
     end_newline: bool,
     /// Whether or not we are in an enum.
     in_enum: Vec<Option<u64>>,
-    /// Whether or not we are in a raw block.
-    in_raw: bool,
+    /// The language and text of the raw block being written.
+    in_raw: Option<(String, String)>,
+    /// The number of cells written in the current table row.
+    table_cells: usize,
+    /// The destination, title and alt text of the image being written.
+    in_image: Option<(String, String, String)>,
+    /// The lowering of inline HTML.
+    html: HtmlLowering,
+    /// The Typst bodies of the footnotes, keyed by their names.
+    footnotes: HashMap<String, String>,
+    /// The labels of the remaining headings, in order.
+    headings: std::vec::IntoIter<Option<String>>,
+    /// The images of the previews of the remaining examples, in order.
+    examples: std::vec::IntoIter<Option<Vec<String>>>,
+    /// The resolver to link inline code and to nest headings with.
+    links: &'l dyn LinkResolver,
+    /// Whether or not we are in a link.
+    in_link: bool,
 }
 
-impl<'a, I, W> TypstWriter<I, W>
+impl<'a, I, W> TypstWriter<'_, I, W>
 where
     I: Iterator<Item = Event<'a>>,
     W: StrWrite,
@@ -78,11 +410,22 @@ where
     /// Creates a new `TypstWriter`.
     fn new(iter: I, writer: W) -> Self {
         Self {
-            iter,
-            writer,
+            iter: iter.peekable(),
+            writer: LineWriter {
+                inner: writer,
+                line_start: true,
+            },
             end_newline: true,
             in_enum: vec![],
-            in_raw: false,
+            in_raw: None,
+            table_cells: 0,
+            in_image: None,
+            html: HtmlLowering::default(),
+            footnotes: HashMap::new(),
+            headings: Vec::new().into_iter(),
+            examples: Vec::new().into_iter(),
+            links: &(),
+            in_link: false,
         }
     }
 
@@ -107,37 +450,59 @@ where
     fn start_tag(&mut self, tag: Tag<'a>) -> io::Result<()> {
         match tag {
             Tag::Paragraph => self.write("\n\n"),
-            Tag::Heading(level, _id, classes) => {
-                let _ = classes;
-
+            Tag::Heading(level, _id, _classes) => {
+                let depth = self.links.heading_depth(level as usize);
                 self.writer
-                    .write_fmt(format_args!("#heading(depth: {})[", level as i32))?;
-
-                Ok(())
+                    .write_fmt(format_args!("#heading(depth: {depth})["))
             }
             Tag::Table(alignments) => {
-                let _ = alignments;
+                if !self.end_newline {
+                    self.write_newline()?;
+                }
 
-                Ok(())
+                let align = alignments
+                    .iter()
+                    .map(|align| match align {
+                        md::Alignment::None => "auto,",
+                        md::Alignment::Left => "left,",
+                        md::Alignment::Center => "center,",
+                        md::Alignment::Right => "right,",
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.writer.write_fmt(format_args!(
+                    "#table(\n  columns: {},\n  align: ({align}),\n",
+                    alignments.len()
+                ))
+            }
+            Tag::TableHead => {
+                self.table_cells = 0;
+                self.write("  docs-table-header(")
+            }
+            Tag::TableRow => {
+                self.table_cells = 0;
+                self.write("  ")
+            }
+            Tag::TableCell => {
+                if self.table_cells > 0 {
+                    self.write(" ")?;
+                }
+                self.table_cells += 1;
+                self.write("[")
             }
-            Tag::TableHead => Ok(()),
-            Tag::TableRow => Ok(()),
-            Tag::TableCell => Ok(()),
             Tag::BlockQuote => self.write("#quote["),
             Tag::CodeBlock(info) => {
                 if !self.end_newline {
                     self.write_newline()?;
                 }
 
-                self.write("``````")?;
-                self.in_raw = true;
-                match info {
-                    CodeBlockKind::Fenced(info) => {
-                        self.write(&info)?;
-                    }
-                    CodeBlockKind::Indented => {}
-                }
-                self.write("\n")
+                // The raw block is written at its end, when its fence is known.
+                let lang = match info {
+                    CodeBlockKind::Fenced(info) => info.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.in_raw = Some((lang, String::new()));
+                Ok(())
             }
             Tag::List(v) => {
                 self.in_enum.push(v);
@@ -157,13 +522,9 @@ where
             Tag::Emphasis => self.write("#emph["),
             Tag::Strong => self.write("#strong["),
             Tag::Strikethrough => self.write("#strike["),
-            Tag::Link(LinkType::Inline, dest, _title) => {
-                self.writer
-                    .write_fmt(format_args!("#link({:?})[", dest.as_ref()))?;
-                Ok(())
-            }
             Tag::Link(
-                LinkType::Reference
+                LinkType::Inline
+                | LinkType::Reference
                 | LinkType::ReferenceUnknown
                 | LinkType::Collapsed
                 | LinkType::CollapsedUnknown
@@ -173,24 +534,28 @@ where
                 dest,
                 _title,
             ) => {
-                self.writer
-                    .write_fmt(format_args!("#link({:?})[", dest.as_ref()))?;
+                self.in_link = true;
+                // Resolved labels are written as `<label>` by the handler.
+                if let Some(label) = dest.strip_prefix('<').and_then(|d| d.strip_suffix('>')) {
+                    self.writer
+                        .write_fmt(format_args!("#link(label({}))[", typst_str(label)))?;
+                } else {
+                    self.writer
+                        .write_fmt(format_args!("#link({})[", typst_str(&dest)))?;
+                }
                 Ok(())
             }
             Tag::Link(LinkType::Email, dest, _title) => {
-                let mailto = format!("mailto:{}", dest.as_ref());
-                self.writer.write_fmt(format_args!("#link({mailto:?})["))?;
+                self.in_link = true;
+                let mailto = typst_str(&format!("mailto:{}", dest.as_ref()));
+                self.writer.write_fmt(format_args!("#link({mailto})["))?;
+                Ok(())
+            }
+            Tag::Image(_link_type, dest, title) => {
+                // The image is written at its end, after its alt text.
+                self.in_image = Some((dest.to_string(), title.to_string(), String::new()));
                 Ok(())
             }
-            Tag::Image(_link_type, _dest, _title) => Ok(()),
-            // Tag::Link(_link_type, dest, _title) => self
-            //     .writer
-            //     .write_fmt(format_args!("\n#link({:?})[", dest.as_ref())),
-            // Tag::Image(_link_type, dest, title) => self.writer.write_fmt(format_args!(
-            //     "\n#image({:?}, alt: {:?})",
-            //     dest.as_ref(),
-            //     title.as_ref()
-            // )),
             Tag::FootnoteDefinition(name) => {
                 let _ = name;
                 Ok(())
@@ -202,20 +567,30 @@ where
     fn end_tag(&mut self, tag: Tag) -> io::Result<()> {
         match tag {
             Tag::Paragraph => self.write("\n")?,
-            Tag::Heading(_level, id, _classes) => {
+            Tag::Heading(..) => {
                 self.write("];")?;
-                if let Some(id) = id {
-                    self.writer.write_fmt(format_args!(" #label({id:?})"))?;
+                if let Some(label) = self.headings.next().flatten() {
+                    self.writer
+                        .write_fmt(format_args!(" #label({})", typst_str(&label)))?;
                 }
             }
-            Tag::Table(_) => {}
-            Tag::TableHead => {}
-            Tag::TableRow => {}
-            Tag::TableCell => {}
+            Tag::Table(_) => self.write(");\n")?,
+            Tag::TableHead => self.write("),\n")?,
+            Tag::TableRow => self.write("\n")?,
+            Tag::TableCell => self.write("],")?,
             Tag::BlockQuote => self.write("];")?,
             Tag::CodeBlock(_) => {
-                self.in_raw = false;
-                self.write("``````\n")?;
+                let Some((lang, text)) = self.in_raw.take() else {
+                    return Ok(());
+                };
+                if is_example(&lang) {
+                    return self.write_example(&text);
+                }
+
+                let fence = raw_fence(&text, 6);
+                self.writer
+                    .write_fmt(format_args!("{fence}{lang}\n{text}{fence}\n"))?;
+                self.end_newline = true;
             }
             Tag::List(_) => {
                 self.in_enum.pop();
@@ -224,14 +599,100 @@ where
             Tag::Emphasis => self.write("];")?,
             Tag::Strong => self.write("];")?,
             Tag::Strikethrough => self.write("];")?,
-            Tag::Link(_, _, _) => self.write("];")?,
-            Tag::Image(_, _, _) => (), // shouldn't happen, handled in start
+            Tag::Link(_, _, _) => {
+                self.in_link = false;
+                self.write("];")?;
+            }
+            Tag::Image(_, _, _) => {
+                let Some((dest, title, alt)) = self.in_image.take() else {
+                    return Ok(());
+                };
+
+                self.writer.write_fmt(format_args!(
+                    "#figure(image({}, alt: {})",
+                    typst_str(&dest),
+                    typst_str(&alt)
+                ))?;
+                if !title.is_empty() {
+                    self.write(", caption: [")?;
+                    escape_typst(&mut self.writer, &title, true)?;
+                    self.write("]")?;
+                }
+                self.write(");")?;
+            }
             Tag::FootnoteDefinition(_) => {}
         }
         Ok(())
     }
 
-    /// Runs the writer, processing all events.
+    /// Writes an example, showing its source next to its preview like the
+    /// examples rendered by `generate`.
+    fn write_example(&mut self, code: &str) -> io::Result<()> {
+        let source = example_source(code);
+        self.writer.write_fmt(format_args!(
+            "#docs-example(raw({}, block: true, lang: \"typ\"),",
+            typst_str(source.trim_end_matches('\n'))
+        ))?;
+        if let Some(images) = self.examples.next().flatten() {
+            self.writer.write_str("docs-preview[")?;
+            for (i, image) in images.iter().enumerate() {
+                let alt = match images.len() {
+                    1 => "Preview".to_owned(),
+                    _ => format!("Preview (page {})", i + 1),
+                };
+                self.writer.write_fmt(format_args!(
+                    "#image({}, alt: {});",
+                    typst_str(image),
+                    typst_str(&alt)
+                ))?;
+            }
+            self.writer.write_str("],")?;
+        }
+        self.writer.write_str(");\n")?;
+        self.end_newline = true;
+        Ok(())
+    }
+
+    /// Writes inline raw, following the rules of the Typst documentation:
+    /// `[..]` is markup, `{..}` is code, and `#call` or `$math$` is markup
+    /// too. Code naming a definition links to it.
+    fn write_code(&mut self, code: &str) -> io::Result<()> {
+        let (text, lang) = inline_code_lang(code);
+
+        let label = if self.in_link {
+            None
+        } else {
+            code_definition(text, lang)
+                .and_then(|name| self.links.resolve_link(&format!("${name}")).ok())
+                .flatten()
+        };
+        if let Some(label) = &label {
+            self.writer
+                .write_fmt(format_args!("#link(label({}))[", typst_str(label)))?;
+        }
+
+        if lang.is_none() && !text.is_empty() && !text.contains('`') {
+            self.write("`")?;
+            self.write(text)?;
+            self.write("`")?;
+        } else {
+            let fence = raw_fence(text, 3);
+            self.write(&fence)?;
+            self.write(lang.unwrap_or_default())?;
+            self.write(" ")?;
+            self.write(text)?;
+            self.write(" ")?;
+            self.write(&fence)?;
+        }
+
+        if label.is_some() {
+            self.write("];")?;
+        }
+        Ok(())
+    }
+
+    /// Runs the writer, processing all events. The unknown HTML tags are
+    /// reported by the [`Handler`], which knows their spans.
     fn run(mut self) -> io::Result<()> {
         use md::Event::*;
         while let Some(event) = self.iter.next() {
@@ -242,139 +703,189 @@ where
                 End(tag) => {
                     self.end_tag(tag)?;
                 }
-                Text(text) => {
-                    if self.in_raw {
-                        self.write(&text)?;
-                    } else {
-                        escape_typst(&mut self.writer, &text)?;
+                Text(text) | Code(text) if self.in_image.is_some() => {
+                    if let Some((_, _, alt)) = &mut self.in_image {
+                        alt.push_str(&text);
                     }
                 }
-
-                // Inline raw. todo: special rules for typst docs.
-                Code(text) => {
-                    let mut chars = text.chars();
-                    let (text, tag) = match (chars.next(), chars.next_back()) {
-                        (Some('['), Some(']')) => (&text[1..text.len() - 1], Some("typ")),
-                        (Some('{'), Some('}')) => (&text[1..text.len() - 1], Some("typc")),
-                        _ => (text.as_ref(), None),
-                    };
-
-                    if let Some(tag) = tag {
-                        self.writer.write_fmt(format_args!("```{tag} "))?;
+                Text(text) => {
+                    // Texts are split at arbitrary positions, e.g. at escapes,
+                    // so adjacent texts are escaped as a whole.
+                    let mut text = text.into_string();
+                    while let Some(Text(next)) = self.iter.next_if(|e| matches!(e, Text(_))) {
+                        text.push_str(&next);
+                    }
+                    if let Some((_, raw)) = &mut self.in_raw {
+                        raw.push_str(&text);
                     } else {
-                        self.write("``` ")?;
+                        let line_start = self.writer.line_start;
+                        self.html.text(&mut self.writer, &text, line_start)?;
                     }
-
-                    self.write(text)?;
-                    self.write(" ```")?;
                 }
 
+                Code(text) => self.write_code(&text)?,
+
                 Html(html) => {
-                    self.write("```raw-html ")?;
-                    self.write(&html)?;
-                    self.write("```")?;
+                    self.html.lower(&mut self.writer, &html)?;
                 }
                 SoftBreak => {
                     self.write_newline()?;
                 }
                 HardBreak => {
-                    self.write("\n\n")?;
+                    self.write("#linebreak();")?;
+                    self.write_newline()?;
                 }
-                // todo: what is this
                 Rule => {
-
-                    // if self.end_newline {
-                    //     self.write("<hr />\n")?;
-                    // } else {
-                    //     self.write("\n<hr />\n")?;
-                    // }
+                    if !self.end_newline {
+                        self.write_newline()?;
+                    }
+                    self.write("#line(length: 100%);\n")?;
                 }
                 FootnoteReference(name) => {
-                    let _ = name;
-                    // let len = self.numbers.len() + 1;
-                    // self.write("<sup class=\"footnote-reference\"><a
-                    // href=\"#")?; escape_html(&mut
-                    // self.writer, &name)?; self.write("\"
-                    // >")?; let number =
-                    // *self.numbers.entry(name).or_insert(len);
-                    // write!(&mut self.writer, "{}", number)?;
-                    // self.write("</a></sup>")?;
+                    // Missing definitions are reported before writing.
+                    let body = self.footnotes.get(name.as_ref()).cloned();
+                    self.write("#footnote[")?;
+                    self.write(body.as_deref().unwrap_or_default())?;
+                    self.write("];")?;
                 }
-                TaskListMarker(true) => {}
-                TaskListMarker(false) => {}
+                TaskListMarker(true) => self.write("☑ ")?,
+                TaskListMarker(false) => self.write("☐ ")?,
             }
         }
+        self.html.finish(&mut self.writer)?;
         Ok(())
     }
 }
 
+/// Gets the text and the language of inline code.
+fn inline_code_lang(code: &str) -> (&str, Option<&'static str>) {
+    let mut chars = code.chars();
+    match (chars.next(), chars.next_back()) {
+        (Some('['), Some(']')) => (&code[1..code.len() - 1], Some("typ")),
+        (Some('{'), Some('}')) => (&code[1..code.len() - 1], Some("typc")),
+        (Some('#'), _) if code.len() > 1 => (code, Some("typ")),
+        (Some('$'), Some('$')) if code.len() > 1 => (code, Some("typ")),
+        _ => (code, None),
+    }
+}
+
+/// Gets the name of the definition inline code refers to, e.g. `calc.max` for
+/// `{calc.max(1, 2)}` or `#calc.max`.
+fn code_definition<'a>(text: &'a str, lang: Option<&str>) -> Option<&'a str> {
+    let name = match lang {
+        Some("typc") => text,
+        Some("typ") => text.strip_prefix('#')?,
+        _ => return None,
+    };
+    let name = match name.split_once('(') {
+        Some((name, args)) if args.ends_with(')') => name,
+        Some(_) => return None,
+        None => name,
+    };
+
+    let is_ident = |part: &str| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    let keyword = matches!(
+        name,
+        "none"
+            | "auto"
+            | "true"
+            | "false"
+            | "let"
+            | "set"
+            | "show"
+            | "context"
+            | "if"
+            | "else"
+            | "for"
+            | "while"
+            | "return"
+            | "break"
+            | "continue"
+            | "import"
+            | "include"
+            | "in"
+            | "not"
+            | "and"
+            | "or"
+            | "as"
+    );
+    (!keyword && name.split('.').all(is_ident)).then_some(name)
+}
+
 /// A handler for Markdown events.
-struct Handler {
-    // outline: Vec<OutlineItem>,
+struct Handler<'a> {
+    /// The resolver for link destinations.
+    links: &'a dyn LinkResolver,
+    /// The references that could not be resolved.
+    problems: Vec<Problem>,
+    /// The spans of the headings, in order.
+    headings: Vec<Range<usize>>,
+    /// The spans of the examples, in order.
+    examples: Vec<Range<usize>>,
+    /// The names and spans of the footnote references.
+    footnotes: Vec<(String, Range<usize>)>,
+    /// The lowering of the inline HTML, finding the unknown tags.
+    html: HtmlLowering,
 }
 
-impl Handler {
+impl<'a> Handler<'a> {
     /// Creates a new `Handler`.
-    fn new() -> Self {
+    fn new(links: &'a dyn LinkResolver) -> Self {
         Self {
-            // outline: vec![],
+            links,
+            problems: vec![],
+            headings: vec![],
+            examples: vec![],
+            footnotes: vec![],
+            html: HtmlLowering::default(),
         }
     }
 
-    /// Handles a Markdown event.
-    fn handle(&mut self, event: &mut md::Event) -> bool {
+    /// Handles a Markdown event found at `span`.
+    fn handle(&mut self, event: &mut md::Event, span: Range<usize>) -> bool {
         match event {
             // Rewrite Markdown images.
             md::Event::Start(md::Tag::Image(_, path, _)) => {
-                *path = self.handle_image(path).into();
-            }
-
-            // Rewrite HTML images.
-            // md::Event::Html(html) if html.starts_with("<img") => {
-            //     let range = html_attr_range(html, "src").unwrap();
-            //     let path = &html[range.clone()];
-            //     let mut buf = html.to_string();
-            //     buf.replace_range(range, &self.handle_image(path));
-            //     *html = buf.into();
-            // }
-
-            // Register HTML headings for the outline.
-            // md::Event::Start(md::Tag::Heading(level, id, _)) => {
-            //     self.handle_heading(id, level);
-            // }
-
-            // Also handle heading closings.
-            // md::Event::End(md::Tag::Heading(level, _, _)) => {
-            //     nest_heading(level, self.nesting());
-            // }
-
-            // Rewrite contributor sections.
-            // md::Event::Html(html) if html.starts_with("<contributors") => {
-            //     let from = html_attr(html, "from").unwrap();
-            //     let to = html_attr(html, "to").unwrap();
-            //     let Some(output) = contributors(self.resolver, from, to) else {
-            //         return false;
-            //     };
-            //     *html = output.raw.into();
-            // }
-
-            // Rewrite links.
-            md::Event::Start(md::Tag::Link(ty, dest, _)) => {
-                assert!(
-                    matches!(
-                        ty,
-                        md::LinkType::Inline
-                            | md::LinkType::Reference
-                            | md::LinkType::ShortcutUnknown
-                            | md::LinkType::Autolink
-                    ),
-                    "unsupported link type: {ty:?}",
-                );
-
-                *dest = match self.handle_link(dest) {
-                    Ok(link) => link.into(),
-                    Err(err) => panic!("invalid link: {dest} ({err})"),
-                };
+                *path = self.handle_image(path, span).into();
+            }
+
+            // Rewrite HTML images, links and contributor sections.
+            md::Event::Html(html) => {
+                // Only the unknown tags are kept, the output is written later.
+                self.html
+                    .lower_at(&mut String::new(), html, span.start)
+                    .unwrap();
+                let mut problems = vec![];
+                *html = resolve_html(html, self.links, &mut problems).into();
+                self.problems
+                    .extend(problems.into_iter().map(|p| p.shifted(span.start)));
+            }
+
+            // Register headings, examples and footnote references to report
+            // problems with them.
+            md::Event::Start(md::Tag::Heading(..)) => self.headings.push(span),
+            md::Event::Start(md::Tag::CodeBlock(CodeBlockKind::Fenced(lang)))
+                if is_example(lang) =>
+            {
+                self.examples.push(span)
+            }
+            md::Event::FootnoteReference(name) => self.footnotes.push((name.to_string(), span)),
+
+            // Rewrite links. Email links are written as `mailto:` links.
+            md::Event::Start(md::Tag::Link(ty, dest, _)) if *ty != md::LinkType::Email => {
+                match self.handle_link(dest) {
+                    Ok(link) => *dest = link.into(),
+                    Err(err) => self.problems.push(Problem::error(
+                        span,
+                        eco_format!("unresolvable reference: {dest} ({err})"),
+                    )),
+                }
             }
 
             _ => {}
@@ -384,123 +895,151 @@ impl Handler {
     }
 
     /// Handles an image link.
-    fn handle_image(&self, link: &str) -> String {
-        // if let Some(data) = typst_dev_assets::get_by_name(link) {
-        //     self.resolver.image(link, data)
-        // } else if let Some(url) = self.resolver.link(link) {
-        //     url
-        // } else {
-        //     panic!("missing image: {link}")
-        // }
-        link.to_string()
-    }
-
-    // fn handle_heading(&mut self, id_slot: &mut Option<&'a str>, level: &mut
-    // md::HeadingLevel) {     nest_heading(level, self.nesting());
-    //     if *level == md::HeadingLevel::H1 {
-    //         return;
-    //     }
-
-    //     let body = self.peeked.as_ref();
-    //     let default = body.map(|text| text.to_kebab_case());
-    //     let has_id = id_slot.is_some();
-
-    //     let id: &'a str = match (&id_slot, default) {
-    //         (Some(id), default) => {
-    //             if Some(*id) == default.as_deref() {
-    //                 eprintln!("heading id #{id} was specified unnecessarily");
-    //             }
-    //             id
-    //         }
-    //         (None, Some(default)) => self.ids.alloc(default).as_str(),
-    //         (None, None) => panic!("missing heading id {}", self.text),
-    //     };
-
-    //     *id_slot = (!id.is_empty()).then_some(id);
-
-    //     // Special case for things like "v0.3.0".
-    //     let name = match &body {
-    //         _ if id.starts_with('v') && id.contains('.') => id.into(),
-    //         Some(body) if !has_id => body.as_ref().into(),
-    //         _ => id.to_title_case().into(),
-    //     };
-
-    //     let mut children = &mut self.outline;
-    //     let mut depth = *level as usize;
-    //     while depth > 2 {
-    //         if !children.is_empty() {
-    //             children = &mut children.last_mut().unwrap().children;
-    //         }
-    //         depth -= 1;
-    //     }
-
-    //     children.push(OutlineItem {
-    //         id: id.into(),
-    //         name,
-    //         children: vec![],
-    //     });
-    // }
-
-    // todo: handle link
-    /// Handles a link.
+    fn handle_image(&mut self, link: &str, span: Range<usize>) -> String {
+        match self.links.resolve_image(link) {
+            Ok(Some(path)) => path,
+            Ok(None) => link.to_string(),
+            Err(err) => {
+                self.problems.push(Problem::error(
+                    span,
+                    eco_format!("unresolvable reference: {link} ({err})"),
+                ));
+                link.to_string()
+            }
+        }
+    }
+
+    /// Handles a link, rewriting links into the documentation to `<label>`.
     fn handle_link(&self, link: &str) -> StrResult<String> {
-        // if let Some(link) = self.resolver.link(link) {
-        //     return Ok(link);
-        // }
+        if let Some(link) = self.links.rewrite_link(link) {
+            return Ok(link);
+        }
 
-        // typst_docs::link::resolve(link, self.resolver.base())
-        Ok(link.to_string())
+        match self.links.resolve_link(link)? {
+            Some(label) => Ok(format!("<{label}>")),
+            None => Ok(link.to_string()),
+        }
     }
 }
 
-/// Creates a table for escaping HTML characters.
-const fn create_typst_escape_table() -> [u8; 256] {
-    let mut table = [0; 256];
-    table[b'"' as usize] = 1;
-    table[b'[' as usize] = 2;
-    table[b']' as usize] = 3;
+/// Creates a table of the characters that are always escaped in markup.
+const fn create_typst_escape_table() -> [bool; 128] {
+    let mut table = [false; 128];
+    let specials = b"\\#$*_@<[]`~";
+    let mut i = 0;
+    while i < specials.len() {
+        table[specials[i] as usize] = true;
+        i += 1;
+    }
     table
 }
 
-/// Escapes HTML characters in a string.
-static HTML_ESCAPE_TABLE: [u8; 256] = create_typst_escape_table();
-/// Escapes HTML characters in a string.
-static HTML_ESCAPES: [&str; 4] = ["", "\\\"", "\\[", "\\]"];
-
-/// Writes the given string to the Write sink, replacing special HTML bytes
-/// (<, >, &, ") by escape sequences.
-pub fn escape_typst<W: StrWrite>(w: W, s: &str) -> io::Result<()> {
-    escape_typst_scalar(w, s)
-}
+/// The characters that are always escaped in markup.
+static TYPST_ESCAPE_TABLE: [bool; 128] = create_typst_escape_table();
 
-/// Escapes HTML characters in a string.
-fn escape_typst_scalar<W: StrWrite>(mut w: W, s: &str) -> io::Result<()> {
-    let bytes = s.as_bytes();
+/// Writes the given text to the Write sink as Typst markup, escaping the
+/// characters that would be interpreted as Typst syntax.
+///
+/// Besides the characters that always start syntax, e.g. `#` or `*`, this
+/// escapes the characters that only do so in context: heading, list, enum and
+/// term markers at the start of a line, comments, links and shorthands like
+/// `--`, `-?`, `-1` or `...`. Quotes are kept, so that they become smart
+/// quotes. Whether the text starts a line is given by `line_start`.
+pub fn escape_typst<W: StrWrite>(mut w: W, s: &str, mut line_start: bool) -> io::Result<()> {
     let mut mark = 0;
-    let mut i = 0;
-    while i < s.len() {
-        match bytes[i..]
-            .iter()
-            .position(|&c| HTML_ESCAPE_TABLE[c as usize] != 0)
-        {
-            Some(pos) => {
-                i += pos;
-            }
-            None => break,
-        }
-        let c = bytes[i];
-        let escape = HTML_ESCAPE_TABLE[c as usize];
-        let escape_seq = HTML_ESCAPES[escape as usize];
-        w.write_str(&s[mark..i])?;
-        w.write_str(escape_seq)?;
-        i += 1;
-        mark = i; // all escaped characters are ASCII
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        // Markers only start syntax when followed by a space.
+        let marker = next.is_none_or(char::is_whitespace);
+        let escape = match c {
+            c if c.is_ascii() && TYPST_ESCAPE_TABLE[c as usize] => true,
+            '=' => {
+                let rest = s[i..].trim_start_matches('=');
+                line_start && rest.chars().next().is_none_or(char::is_whitespace)
+            }
+            '+' => line_start && marker,
+            '-' => (line_start && marker) || matches!(next, Some('-' | '?' | '0'..='9')),
+            '/' => (line_start && marker) || matches!(next, Some('/' | '*')),
+            '.' => s[i + 1..].starts_with("..") || (marker && enum_number(&s[..i])),
+            _ => false,
+        };
+        if escape {
+            w.write_str(&s[mark..i])?;
+            w.write_str("\\")?;
+            mark = i;
+        }
+
+        line_start = c == '\n' || (line_start && c.is_whitespace());
     }
     w.write_str(&s[mark..])
 }
 
+/// A writer tracking whether the text written next starts a line of markup,
+/// i.e. follows a newline or the start of a content block, and may begin
+/// headings or lists.
+struct LineWriter<W> {
+    /// The writer to write to.
+    inner: W,
+    /// Whether the text written next starts a line.
+    line_start: bool,
+}
+
+impl<W: StrWrite> StrWrite for LineWriter<W> {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        if let Some(last) = s.trim_end_matches([' ', '\t']).chars().next_back() {
+            self.line_start = matches!(last, '\n' | '[');
+        }
+        self.inner.write_str(s)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> io::Result<()> {
+        self.write_str(&args.to_string())
+    }
+}
+
+/// Whether a text ends with the number of an enumeration item, e.g. `1` in
+/// `1. item`.
+fn enum_number(s: &str) -> bool {
+    let line = s.rsplit('\n').next().unwrap_or(s).trim_start();
+    !line.is_empty() && line.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Makes a Typst string literal containing the given text.
+pub fn typst_str(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '"' => result.push_str("\\\""),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Makes a fence for a raw element containing the given text, which is at
+/// least `min` backticks long and longer than any run of backticks in the
+/// text.
+pub fn raw_fence(text: &str, min: usize) -> String {
+    let ticks = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat((ticks + 1).max(min))
+}
+
 #[cfg(test)]
 mod tests {
+    use insta::assert_snapshot;
+
     use super::*;
 
     #[test]
@@ -529,6 +1068,16 @@ fn main() {
         let result = md_to_typst(input).unwrap();
         assert_eq!(result, expected);
 
+        let input = "Roses are red,\\\nViolets are blue.\n\n---\n\n- [x] Done\n- [ ] Todo";
+        let expected = r##"
+
+Roses are red,#linebreak();
+Violets are blue.
+#line(length: 100%);
+#list.item[☑ Done];#list.item[☐ Todo];"##;
+        let result = md_to_typst(input).unwrap();
+        assert_eq!(result, expected);
+
         let input = r"「内联盒子」（[box](#x-term-box)）";
         let expected = r##"
 
@@ -537,4 +1086,427 @@ fn main() {
         let result = md_to_typst(input).unwrap();
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_md_to_typst_with_links() {
+        /// Resolves `$` links to labels named after their target.
+        struct Links;
+
+        impl LinkResolver for Links {
+            fn resolve_link(&self, link: &str) -> StrResult<Option<String>> {
+                match link.strip_prefix('$') {
+                    Some("missing") => Err("unknown page".into()),
+                    Some(target) => Ok(Some(target.replace('/', "."))),
+                    None => Ok(None),
+                }
+            }
+
+            fn rewrite_link(&self, link: &str) -> Option<String> {
+                link.strip_prefix("$universe")
+                    .map(|rest| format!("https://typst.app/universe{rest}"))
+            }
+        }
+
+        let input = "See the [table guide]($guides/table-guide) or [Typst](https://typst.app).";
+        let expected = r##"
+
+See the #link(label("guides.table-guide"))[table guide]; or #link("https://typst.app")[Typst];.
+"##;
+        let result = md_to_typst_with(input, &Links, &mut vec![]).unwrap();
+        assert_eq!(result, expected);
+
+        let input = r#"[Universe]($universe/search) <a href="$universe">packages</a>"#;
+        let result = md_to_typst_with(input, &Links, &mut vec![]).unwrap();
+        assert_snapshot!(result, @r#"
+        #link("https://typst.app/universe/search")[Universe]; #link("https://typst.app/universe")[packages];
+        "#);
+
+        let mut problems = vec![];
+        md_to_typst_with("[a]($missing) [b]($missing)", &Links, &mut problems).unwrap();
+        let message = "unresolvable reference: $missing (unknown page)";
+        assert_eq!(
+            problems,
+            vec![
+                Problem::error(0..13, message),
+                Problem::error(14..27, message)
+            ]
+        );
+
+        let mut problems = vec![];
+        md_to_typst_with("[a][b] [c][]", &Links, &mut problems).unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                Problem::error(0..6, "unsupported link type: Reference"),
+                Problem::error(7..10, "unsupported link type: Collapsed"),
+            ]
+        );
+
+        let input = r#"Press <kbd>Ctrl</kbd>, see <a href="$guides">guides</a>.
+
+<div class="info-box">
+
+Use *this*.<marquee>!</marquee>
+</div>"#;
+        let expected = r##"
+
+Press #docs-kbd[Ctrl];, see #link(label("guides"))[guides];.
+#docs-info-box[
+
+
+Use #emph[this];.!
+];"##;
+        let mut problems = vec![];
+        let result = md_to_typst_with(input, &Links, &mut problems).unwrap();
+        assert_eq!(result, expected);
+        assert_eq!(
+            problems,
+            vec![Problem::warning(
+                Some(93..102),
+                "unknown HTML tag <marquee>"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_md_to_typst_inline_code() {
+        /// Resolves the definitions `text` and `{calc.max}`.
+        struct Definitions;
+
+        impl LinkResolver for Definitions {
+            fn resolve_link(&self, link: &str) -> StrResult<Option<String>> {
+                match link {
+                    "$text" | "$calc.max" => Ok(Some(link[1..].to_owned())),
+                    _ => Err("unknown definition".into()),
+                }
+            }
+        }
+
+        let input = "`{calc.max(1, 2)}` `#text(red)` `[*strong*]` `$x^2$` `{none}` \
+                     `` a`b `` `{x}` [`{text}`]($text)";
+        let result = md_to_typst_with(input, &Definitions, &mut vec![]).unwrap();
+        assert_snapshot!(result, @r#"
+        #link(label("calc.max"))[```typc calc.max(1, 2) ```]; #link(label("text"))[```typ #text(red) ```]; ```typ *strong* ``` ```typ $x^2$ ``` ```typc none ``` ``` a`b ``` ```typc x ``` #link(label("text"))[```typc text ```];
+        "#);
+    }
+
+    #[test]
+    fn test_md_to_typst_tables() {
+        // From the guide for LaTeX users.
+        let input = r#"| Element          | LaTeX                     | Typst                  | See        |
+|:-----------------|:--------------------------|:-----------------------|:-----------|
+| Strong emphasis  | `\textbf{strong}`         | `[*strong*]`           | [`strong`] |
+| Monospace / code | `\texttt{print(1)}`       | ``[`print(1)`]``       | [`raw`]    |
+| Equation         | `$x$`, `align` / `equation` environments | `[$x$]`, `[$ x = y $]` | [`equation`]($math.equation) |"#;
+        assert_snapshot!(md_to_typst(input).unwrap(), @r#"
+        #table(
+          columns: 4,
+          align: (left, left, left, left,),
+          docs-table-header([Element], [LaTeX], [Typst], [See],),
+          [Strong emphasis], [`\textbf{strong}`], [```typ *strong* ```], [#link("$strong")[`strong`];],
+          [Monospace / code], [`\texttt{print(1)}`], [```typ `print(1)` ```], [#link("$raw")[`raw`];],
+          [Equation], [```typ $x$ ```, `align` / `equation` environments], [```typ $x$ ```, ```typ $ x = y $ ```], [#link("$math.equation")[`equation`];],
+        );
+        "#);
+
+        // From the table guide.
+        let input = r#"Before the table.
+
+| Left | Center | Right | Default |
+|:-----|:------:|------:|---------|
+| 1    | *two*  | 3     |         |"#;
+        assert_snapshot!(md_to_typst(input).unwrap(), @r#"
+        Before the table.
+        #table(
+          columns: 4,
+          align: (left, center, right, auto,),
+          docs-table-header([Left], [Center], [Right], [Default],),
+          [1], [#emph[two];], [3], [],
+        );
+        "#);
+    }
+
+    #[test]
+    fn test_md_to_typst_footnotes() {
+        let input =
+            "Typst[^1] is fast[^fast].\n\n[^fast]: Really *fast*.\n\n[^1]: A typesetting system.";
+        let expected =
+            "\n\nTypst#footnote[A typesetting system.]; is fast#footnote[Really #emph[fast];.];.\n";
+        let result = md_to_typst_with(input, &(), &mut vec![]).unwrap();
+        assert_eq!(result, expected);
+
+        /// Links definitions to their labels.
+        struct Definitions;
+
+        impl LinkResolver for Definitions {
+            fn resolve_link(&self, link: &str) -> StrResult<Option<String>> {
+                Ok(link.strip_prefix('$').map(str::to_owned))
+            }
+        }
+
+        let input = "Typst[^1].\n\n[^1]: See the [guides]($guides) and `{calc.max}`.";
+        insta::assert_snapshot!(md_to_typst_with(input, &Definitions, &mut vec![]).unwrap(), @r#"
+
+
+        Typst#footnote[See the #link(label("guides"))[guides]; and #link(label("calc.max"))[```typc calc.max ```];.];.
+        "#);
+
+        let mut problems = vec![];
+        md_to_typst_with("Typst[^missing].", &(), &mut problems).unwrap();
+        assert_eq!(
+            problems,
+            vec![Problem::error(
+                5..15,
+                "missing footnote definition: [^missing]"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_md_to_typst_images() {
+        /// Moves images into the assets of the book.
+        struct Images;
+
+        impl LinkResolver for Images {
+            fn resolve_link(&self, _link: &str) -> StrResult<Option<String>> {
+                Ok(None)
+            }
+
+            fn resolve_image(&self, src: &str) -> StrResult<Option<String>> {
+                match src {
+                    "missing.png" => Err("missing image: missing.png".into()),
+                    _ => Ok(Some(format!("assets/{src}"))),
+                }
+            }
+        }
+
+        let input = r#"![The `typst` app](1-writing-app.png "Writing [in] the app")"#;
+        let expected = r##"
+
+#figure(image("assets/1-writing-app.png", alt: "The typst app"), caption: [Writing \[in\] the app]);
+"##;
+        let result = md_to_typst_with(input, &Images, &mut vec![]).unwrap();
+        assert_eq!(result, expected);
+
+        let mut problems = vec![];
+        md_to_typst_with("![Tiger](missing.png)", &Images, &mut problems).unwrap();
+        assert_eq!(
+            problems,
+            vec![Problem::error(
+                0..21,
+                "unresolvable reference: missing.png (missing image: missing.png)"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_md_to_typst_examples() {
+        /// Finds the images of the rendered examples.
+        struct Examples;
+
+        impl LinkResolver for Examples {
+            fn resolve_link(&self, _link: &str) -> StrResult<Option<String>> {
+                Ok(None)
+            }
+
+            fn resolve_example(&self, code: &str) -> StrResult<Option<Vec<String>>> {
+                match code {
+                    "#set page(width: 4cm)\n= Hi\n" => Ok(Some(vec!["assets/1f.png".into()])),
+                    _ => Err("example was not rendered".into()),
+                }
+            }
+        }
+
+        let input = "```example\n>>> #set text(1em)\n<<< #set page(width: 4cm)\n= Hi\n```\n";
+        let result = md_to_typst_with(input, &Examples, &mut vec![]).unwrap();
+        assert_snapshot!(result, @r##"
+        #docs-example(raw("#set page(width: 4cm)\n= Hi", block: true, lang: "typ"),docs-preview[#image("assets/1f.png", alt: "Preview");],);
+        "##);
+
+        let mut problems = vec![];
+        let result = md_to_typst_with("```example\n= Ho\n```", &Examples, &mut problems).unwrap();
+        assert_snapshot!(result, @r#"#docs-example(raw("= Ho", block: true, lang: "typ"),);"#);
+        assert_eq!(
+            problems,
+            vec![Problem::warning(Some(0..19), "example was not rendered")]
+        );
+    }
+
+    #[test]
+    fn test_escape_typst() {
+        let escape = |s: &str| {
+            let mut result = String::new();
+            escape_typst(&mut result, s, true).unwrap();
+            result
+        };
+
+        assert_eq!(escape("#set text(red)"), r"\#set text(red)");
+        assert_eq!(escape("= Not a heading"), r"\= Not a heading");
+        assert_eq!(escape("=Heading"), "=Heading");
+        assert_eq!(escape("1. Not an enum"), r"1\. Not an enum");
+        assert_eq!(escape("a - b -- c -1 ..."), r"a - b \-- c \-1 \...");
+        assert_eq!(escape("https://typst.app"), r"https:\//typst.app");
+        assert_eq!(escape("$x$ and *y* @z <w>"), r"\$x\$ and \*y\* \@z \<w>");
+        assert_eq!(escape("it's \"quoted\""), "it's \"quoted\"");
+        assert_eq!(escape("1 + 1 = 2"), "1 + 1 = 2");
+    }
+
+    #[test]
+    fn test_typst_str() {
+        assert_eq!(typst_str("plain"), r#""plain""#);
+        assert_eq!(typst_str("a \"b\" \\c\n"), r#""a \"b\" \\c\n""#);
+        assert_eq!(typst_str("\0"), r#""\u{0}""#);
+    }
+
+    #[test]
+    fn test_raw_fence() {
+        assert_eq!(raw_fence("code", 3), "```");
+        assert_eq!(raw_fence("a ```` b", 3), "`````");
+        assert_eq!(raw_fence("a ```` b", 6), "``````");
+    }
+
+    /// Generates arbitrary single-line texts, including all ASCII
+    /// punctuation, with a fixed seed.
+    fn arbitrary_texts() -> impl Iterator<Item = String> {
+        let alphabet: Vec<char> = ('!'..='~')
+            .filter(|c| !c.is_ascii_alphanumeric())
+            .chain("aZ09 \t中文é".chars())
+            .collect();
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..500).map(move |_| {
+            let len = next() % 24;
+            (0..len)
+                .map(|_| alphabet[(next() % alphabet.len() as u64) as usize])
+                .collect()
+        })
+    }
+
+    /// Extracts the plain text of parsed Typst markup, or `None` if the
+    /// markup contains anything but text.
+    fn plain_text(node: &typst::syntax::SyntaxNode, text: &mut String) -> Option<()> {
+        use typst::syntax::SyntaxKind;
+
+        match node.kind() {
+            SyntaxKind::Markup => {
+                for child in node.children() {
+                    plain_text(child, text)?;
+                }
+            }
+            // Quotes are kept as smart quotes.
+            SyntaxKind::Text | SyntaxKind::Space | SyntaxKind::SmartQuote => {
+                text.push_str(node.text())
+            }
+            SyntaxKind::Escape => text.push_str(&node.text()[1..]),
+            SyntaxKind::Parbreak => text.push_str("\n\n"),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Parses Typst markup and extracts its plain text.
+    fn parse_plain_text(markup: &str) -> Option<String> {
+        let root = typst::syntax::parse(markup);
+        if root.erroneous() {
+            return None;
+        }
+        let mut text = String::new();
+        plain_text(&root, &mut text)?;
+        Some(text)
+    }
+
+    #[test]
+    fn test_escape_typst_round_trip() {
+        for input in arbitrary_texts() {
+            let mut markup = String::new();
+            escape_typst(&mut markup, &input, true).unwrap();
+            assert_eq!(
+                parse_plain_text(&markup).as_deref(),
+                Some(input.as_str()),
+                "{input:?} was escaped as {markup:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_md_to_typst_round_trip() {
+        let runs = ["a--b", "x -- y", "a-", "-1", "a...b", "x // y", "a -? b"].map(String::from);
+        for input in arbitrary_texts().chain(runs) {
+            // Leading whitespace would start a code block.
+            let input = input.trim();
+            let mut md = String::new();
+            for (i, c) in input.char_indices() {
+                // Punctuation that is no Markdown syntax within a line is
+                // kept, unless it could start an ordered list.
+                let inline = matches!(c, '-' | '.' | '/' | '+' | '=' | '?')
+                    && i > 0
+                    && !input[..i].bytes().all(|b| b.is_ascii_digit());
+                if c.is_ascii_punctuation() && !inline {
+                    md.push('\\');
+                }
+                md.push(c);
+            }
+
+            let markup = md_to_typst(&md).unwrap();
+            let text = parse_plain_text(&markup);
+            assert_eq!(
+                text.as_deref().map(str::trim),
+                Some(input),
+                "{md:?} was converted to {markup:?}"
+            );
+        }
+
+        // Texts split at escapes are escaped as a whole, and only line starts
+        // are escaped as such.
+        let markup = md_to_typst(r"a-\-b x -\- y *a* - b").unwrap();
+        assert_eq!(markup.trim(), r"a\--b x \-- y #emph[a]; - b");
+    }
+
+    #[test]
+    fn test_md_to_typst_headings() {
+        /// Labels headings on the page `page`, nesting them one level deeper.
+        struct Headings;
+
+        impl LinkResolver for Headings {
+            fn resolve_link(&self, _link: &str) -> StrResult<Option<String>> {
+                Ok(None)
+            }
+
+            fn resolve_heading(
+                &self,
+                _index: usize,
+                _count: usize,
+                id: &str,
+            ) -> StrResult<Option<String>> {
+                match id {
+                    "extra" => Err("heading is not in the outline of page".into()),
+                    _ => Ok(Some(format!("page.{id}"))),
+                }
+            }
+
+            fn heading_depth(&self, level: usize) -> usize {
+                level + 1
+            }
+        }
+
+        let input = "# Title\n## Getting Started\n### Custom {#custom-id}\n## The `calc` Module";
+        let expected = r##"#heading(depth: 2)[Title];#heading(depth: 3)[Getting Started]; #label("page.getting-started")#heading(depth: 4)[Custom]; #label("page.custom-id")#heading(depth: 3)[The `calc` Module]; #label("page.the-calc-module")"##;
+        let result = md_to_typst_with(input, &Headings, &mut vec![]).unwrap();
+        assert_eq!(result, expected);
+
+        let mut problems = vec![];
+        md_to_typst_with("Text\n\n## Extra", &Headings, &mut problems).unwrap();
+        assert_eq!(
+            problems,
+            vec![Problem::error(
+                6..14,
+                "unresolvable reference: #extra (heading is not in the outline of page)"
+            )]
+        );
+    }
 }
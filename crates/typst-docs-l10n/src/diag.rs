@@ -0,0 +1,168 @@
+//! Diagnostics of the conversion of the documentation.
+
+use std::fmt;
+use std::ops::Range;
+
+use serde::Serialize;
+use typst::ecow::EcoString;
+
+use crate::markdown::split_markdown;
+
+/// The severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The converted text is broken.
+    Error,
+    /// The converted text may lose some content.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => f.write_str("error"),
+            Self::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// A problem found while converting a text, located by a byte range in the
+/// text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The severity of the problem.
+    pub severity: Severity,
+    /// The byte range of the problem in the text, if known.
+    pub span: Option<Range<usize>>,
+    /// The description of the problem.
+    pub message: EcoString,
+}
+
+impl Problem {
+    /// Creates an error at `span`.
+    pub fn error(span: Range<usize>, message: impl Into<EcoString>) -> Self {
+        Self {
+            severity: Severity::Error,
+            span: Some(span),
+            message: message.into(),
+        }
+    }
+
+    /// Creates a warning, optionally at `span`.
+    pub fn warning(span: Option<Range<usize>>, message: impl Into<EcoString>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Moves the span by `offset`, e.g. from a snippet to the text containing
+    /// it.
+    pub fn shifted(mut self, offset: usize) -> Self {
+        self.span = self.span.map(|span| span.start + offset..span.end + offset);
+        self
+    }
+}
+
+/// A problem found in a translation of the documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// The severity of the problem.
+    pub severity: Severity,
+    /// The key of the translation.
+    pub key: String,
+    /// The index of the paragraph of the translation, for translations made
+    /// of several paragraphs.
+    pub paragraph: Option<usize>,
+    /// The byte range of the problem in the paragraph, or in the translation
+    /// if it has a single paragraph.
+    pub span: Option<Range<usize>>,
+    /// The description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates an error about a whole translation.
+    pub fn error(key: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            key: key.to_owned(),
+            paragraph: None,
+            span: None,
+            message: message.into(),
+        }
+    }
+
+    /// Locates a problem found in the translation `text` with the key `key`,
+    /// relative to the paragraph it is in.
+    pub fn new(key: &str, text: &str, problem: Problem) -> Self {
+        let mut paragraph = None;
+        let mut span = problem.span;
+        if let Some(range) = &span {
+            let pars = split_markdown(text);
+            if pars.len() > 1 {
+                let offset = |par: &str| par.as_ptr() as usize - text.as_ptr() as usize;
+                let index = pars
+                    .iter()
+                    .rposition(|par| offset(par) <= range.start)
+                    .unwrap_or_default();
+                let start = offset(pars[index]);
+                paragraph = Some(index);
+                span = Some(range.start - start..range.end - start);
+            }
+        }
+
+        Self {
+            severity: problem.severity,
+            key: key.to_owned(),
+            paragraph,
+            span,
+            message: problem.message.into(),
+        }
+    }
+
+    /// Whether the diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.key)?;
+        if let Some(paragraph) = self.paragraph {
+            write!(f, ", paragraph {paragraph}")?;
+        }
+        if let Some(span) = &self.span {
+            write!(f, ", {}..{}", span.start, span.end)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate_diagnostic() {
+        let text = "First paragraph.\n\nSecond [link]($missing).";
+        let problem = Problem::error(25..41, "unresolvable reference: $missing (unknown page)");
+        let diag = Diagnostic::new("guides.body", text, problem);
+        assert_eq!(diag.paragraph, Some(1));
+        assert_eq!(diag.span, Some(7..23));
+        assert_eq!(
+            diag.to_string(),
+            "error: guides.body, paragraph 1, 7..23: unresolvable reference: $missing (unknown page)"
+        );
+
+        let problem = Problem::warning(None, "unknown HTML tag <marquee>");
+        let diag = Diagnostic::new("guides.title", "Guides", problem);
+        assert_eq!(
+            diag.to_string(),
+            "warning: guides.title: unknown HTML tag <marquee>"
+        );
+    }
+}
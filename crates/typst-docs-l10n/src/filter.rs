@@ -0,0 +1,300 @@
+//! Selects the pages of the documentation to translate and to build.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{BodyMdModel, PageMdModel};
+
+/// Selects pages by their route, body kind or part.
+///
+/// A page is kept unless it matches an exclude rule, in which case its
+/// children are left out too. If there are include rules, a page is only kept
+/// if it, one of its ancestors or one of its descendants matches one of them.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct PageFilter {
+    /// Only keep the pages matching one of these rules, with their ancestors
+    /// and children. A rule is a route glob like `/tutorial/**`, a body kind
+    /// like `kind:symbols`, or a part like `part:Reference`.
+    #[arg(long = "include", value_name = "RULE")]
+    pub include: Vec<PageRule>,
+    /// Leave out the pages matching one of these rules, with their children.
+    #[arg(long = "exclude", value_name = "RULE")]
+    pub exclude: Vec<PageRule>,
+}
+
+/// A rule matching pages of the documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageRule {
+    /// Matches the routes against a glob, where `*` matches within a segment
+    /// and `**` matches across segments.
+    Route(String),
+    /// Matches the kind of the body, e.g. `func` or `symbols`.
+    Kind(String),
+    /// Matches the part of the sidebar the page is in, e.g. `Reference`.
+    Part(String),
+}
+
+impl PageFilter {
+    /// Removes the pages left out by the filter from `pages`.
+    pub fn apply(&self, pages: &mut Vec<PageMdModel>) {
+        self.retain(pages, None, false);
+    }
+
+    /// Retains the selected `pages`, which are siblings in the part `part`.
+    /// Returns whether any page is kept.
+    ///
+    /// If the page starting a part is left out, the part moves to the next
+    /// kept page in it, so that the part is still introduced.
+    fn retain(
+        &self,
+        pages: &mut Vec<PageMdModel>,
+        mut part: Option<String>,
+        included: bool,
+    ) -> bool {
+        // The part started by a page that has not been kept yet.
+        let mut unplaced = None;
+        pages.retain_mut(|page| {
+            if let Some(page_part) = &page.part {
+                part = Some(page_part.to_string());
+                unplaced = Some(page_part.clone());
+            }
+            let matches = |rule: &PageRule| rule.matches(page, part.as_deref());
+            if self.exclude.iter().any(matches) {
+                return false;
+            }
+
+            let included = included || self.include.is_empty() || self.include.iter().any(matches);
+            let children = self.retain(&mut page.children, part.clone(), included);
+            let kept = included || children;
+            if kept {
+                page.part = unplaced.take();
+            }
+            kept
+        });
+        !pages.is_empty()
+    }
+}
+
+impl PageRule {
+    /// Whether the rule matches a page in the part `part`.
+    fn matches(&self, page: &PageMdModel, part: Option<&str>) -> bool {
+        match self {
+            Self::Route(glob) => glob_matches(glob.as_bytes(), page.route.as_bytes()),
+            Self::Kind(kind) => body_kind(&page.body) == kind,
+            Self::Part(name) => part == Some(name.as_str()),
+        }
+    }
+}
+
+impl FromStr for PageRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        match rule.split_once(':') {
+            Some(("route", glob)) => Ok(Self::Route(glob.to_owned())),
+            Some(("kind", kind)) if KINDS.contains(&kind) => Ok(Self::Kind(kind.to_owned())),
+            Some(("kind", kind)) => Err(format!(
+                "unknown body kind `{kind}`, expected one of {}",
+                KINDS.join(", ")
+            )),
+            Some(("part", part)) => Ok(Self::Part(part.to_owned())),
+            Some((prefix, _)) => Err(format!(
+                "unknown rule `{prefix}:`, expected `route:`, `kind:` or `part:`"
+            )),
+            None => Ok(Self::Route(rule.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for PageRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Route(glob) => write!(f, "route:{glob}"),
+            Self::Kind(kind) => write!(f, "kind:{kind}"),
+            Self::Part(part) => write!(f, "part:{part}"),
+        }
+    }
+}
+
+/// The kinds of page bodies.
+const KINDS: &[&str] = &[
+    "html", "category", "func", "group", "type", "symbols", "packages",
+];
+
+/// Gets the kind of a page body.
+fn body_kind(body: &BodyMdModel) -> &'static str {
+    match body {
+        BodyMdModel::Html(_) => "html",
+        BodyMdModel::Category(_) => "category",
+        BodyMdModel::Func(_) => "func",
+        BodyMdModel::Group(_) => "group",
+        BodyMdModel::Type(_) => "type",
+        BodyMdModel::Symbols(_) => "symbols",
+        BodyMdModel::Packages(_) => "packages",
+    }
+}
+
+/// Matches a route against a glob, where `*` matches within a segment and
+/// `**` matches across segments.
+fn glob_matches(glob: &[u8], route: &[u8]) -> bool {
+    match glob {
+        [] => route.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            // `/**/` also matches a single `/`.
+            let rest = match rest {
+                [b'/', after @ ..] if glob_matches(after, route) => return true,
+                rest => rest,
+            };
+            (0..=route.len()).any(|i| glob_matches(rest, &route[i..]))
+        }
+        [b'*', rest @ ..] => {
+            let segment = route.iter().position(|&c| c == b'/').unwrap_or(route.len());
+            (0..=segment).any(|i| glob_matches(rest, &route[i..]))
+        }
+        [c, rest @ ..] => route.first() == Some(c) && glob_matches(rest, &route[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlMd;
+
+    /// Makes a page with an HTML body.
+    fn page(route: &str, part: Option<&str>, children: Vec<PageMdModel>) -> PageMdModel {
+        PageMdModel {
+            route: route.into(),
+            title: route.into(),
+            description: "".into(),
+            part: part.map(Into::into),
+            outline: vec![],
+            body: BodyMdModel::Html(HtmlMd::Md("".into())),
+            children,
+        }
+    }
+
+    /// Filters the documentation, returning the routes of the kept pages.
+    fn routes(include: &[&str], exclude: &[&str]) -> Vec<String> {
+        /// Collects the routes of pages and their children.
+        fn collect(pages: &[PageMdModel], routes: &mut Vec<String>) {
+            for page in pages {
+                routes.push(page.route.to_string());
+                collect(&page.children, routes);
+            }
+        }
+
+        let mut pages = vec![
+            page("/", None, vec![]),
+            page(
+                "/tutorial/",
+                Some("Tutorial"),
+                vec![page("/tutorial/writing-in-typst/", None, vec![])],
+            ),
+            page(
+                "/reference/",
+                Some("Reference"),
+                vec![page("/reference/foundations/", None, vec![])],
+            ),
+            page(
+                "/changelog/",
+                None,
+                vec![PageMdModel {
+                    body: BodyMdModel::Html(HtmlMd::Md(
+                        r#"<contributors from="v0.12.0" to="v0.13.0" />"#.into(),
+                    )),
+                    ..page("/changelog/0.13.0/", None, vec![])
+                }],
+            ),
+        ];
+        let filter = PageFilter {
+            include: include.iter().map(|rule| rule.parse().unwrap()).collect(),
+            exclude: exclude.iter().map(|rule| rule.parse().unwrap()).collect(),
+        };
+        filter.apply(&mut pages);
+
+        let mut routes = vec![];
+        collect(&pages, &mut routes);
+        routes
+    }
+
+    #[test]
+    fn test_page_filter() {
+        assert_eq!(routes(&[], &[]).len(), 7);
+        assert_eq!(
+            routes(&["/tutorial/**"], &[]),
+            ["/tutorial/", "/tutorial/writing-in-typst/"]
+        );
+        assert_eq!(
+            routes(&["/*/foundations/"], &[]),
+            ["/reference/", "/reference/foundations/"]
+        );
+        assert_eq!(
+            routes(&["/changelog/**"], &[]),
+            ["/changelog/", "/changelog/0.13.0/"]
+        );
+        assert_eq!(
+            routes(&["part:Reference"], &["route:/reference/*/"]),
+            ["/reference/", "/changelog/", "/changelog/0.13.0/"]
+        );
+        assert_eq!(
+            routes(&[], &["/changelog/**", "kind:html"]),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_page_filter_carries_parts() {
+        let mut pages = vec![
+            page("/", None, vec![]),
+            page("/reference/", Some("Reference"), vec![]),
+            page("/reference/foundations/", None, vec![]),
+            page("/reference/model/", None, vec![]),
+            page("/guides/", Some("Guides"), vec![]),
+        ];
+        let filter = PageFilter {
+            include: vec![],
+            exclude: vec!["/reference/".parse().unwrap()],
+        };
+        filter.apply(&mut pages);
+
+        let parts: Vec<_> = pages
+            .iter()
+            .map(|page| (page.route.as_str(), page.part.as_deref()))
+            .collect();
+        assert_eq!(
+            parts,
+            [
+                ("/", None),
+                ("/reference/foundations/", Some("Reference")),
+                ("/reference/model/", None),
+                ("/guides/", Some("Guides")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_page_rule() {
+        assert_eq!(
+            "/changelog/**".parse(),
+            Ok(PageRule::Route("/changelog/**".into()))
+        );
+        assert_eq!("kind:func".parse(), Ok(PageRule::Kind("func".into())));
+        assert!("kind:function".parse::<PageRule>().is_err());
+        assert!("title:Guides".parse::<PageRule>().is_err());
+        assert_eq!(
+            "part:Reference".parse::<PageRule>().unwrap().to_string(),
+            "part:Reference"
+        );
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        let matches = |glob: &str, route: &str| glob_matches(glob.as_bytes(), route.as_bytes());
+        assert!(matches("/tutorial/**", "/tutorial/"));
+        assert!(matches("/tutorial/**", "/tutorial/a/b/"));
+        assert!(matches("/**/changelog/**", "/changelog/0.13.0/"));
+        assert!(matches("/reference/*/", "/reference/foundations/"));
+        assert!(!matches("/reference/*/", "/reference/foundations/array/"));
+        assert!(!matches("/tutorial/**", "/tutorials/"));
+    }
+}
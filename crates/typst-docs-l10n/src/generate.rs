@@ -1,10 +1,20 @@
 //! Generates Typst Documentation
 
-use core::fmt;
+use core::fmt::{self, Write as _};
+use std::cell::RefCell;
 
+use anyhow::Context;
 use tinymist_l10n::TranslationMapSet;
+use typst::diag::StrResult;
+use typst::ecow::eco_format;
 
-use crate::{convert::md_to_typst, *};
+use crate::assets::BookAssets;
+use crate::commits::{contributors, CommitLog, Contributors};
+use crate::convert::{md_to_typst_with, typst_str, LinkResolver};
+use crate::diag::{Diagnostic, Problem};
+use crate::html::{html_text, html_to_typst_with};
+use crate::link::LinkIndex;
+use crate::*;
 
 /// A typed index for a Typst page.
 #[derive(Debug, Clone, Copy)]
@@ -13,8 +23,13 @@ pub struct TypstPageIdx(usize);
 /// This module generates the Typst documentation in a format that can be
 /// used by the Typst documentation generator.
 pub struct TypstPage {
+    /// The dot-path key of the page.
+    key: String,
     /// The children pages.
     children: Vec<TypstPageIdx>,
+    /// Whether the page begins a part, which lasts until the next part or the
+    /// last of its siblings.
+    part: bool,
     /// The body of the page.
     body: TypstContent,
 }
@@ -24,17 +39,48 @@ impl TypstPage {
     pub fn write(&self, ctx: &GenContext, result: &mut impl Writer) -> anyhow::Result<()> {
         write_pagebreak(result)?;
 
-        self.body.write(result)?;
+        self.body.write(ctx, &self.key, result)?;
 
+        let mut parts = PartBlocks::default();
         for child in &self.children {
-            write_pagebreak(result)?;
             let child = ctx.get_page(*child);
+            parts.begin(child, result);
+            write_pagebreak(result)?;
             child.write(ctx, result)?;
         }
+        parts.end(result);
         Ok(())
     }
 }
 
+/// Writes the pages of each part in a content block, so that the heading
+/// numbering set by the part divider ends with the part.
+#[derive(Default)]
+struct PartBlocks {
+    /// Whether the block of a part is open.
+    open: bool,
+}
+
+impl PartBlocks {
+    /// Opens the block of a part before a page beginning it, closing the
+    /// previous one.
+    fn begin(&mut self, page: &TypstPage, result: &mut impl Writer) {
+        if page.part {
+            self.end(result);
+            result.push_str("\n\n#[");
+            self.open = true;
+        }
+    }
+
+    /// Closes the open block, if any.
+    fn end(&mut self, result: &mut impl Writer) {
+        if self.open {
+            result.push_str("\n\n]\n\n");
+            self.open = false;
+        }
+    }
+}
+
 /// Writes a page break to the output.
 fn write_pagebreak(result: &mut impl Writer) -> anyhow::Result<()> {
     result.push_str("\n\n#pagebreak();\n\n");
@@ -47,32 +93,131 @@ pub struct GenContext<'a> {
     // target: Target,
     /// The translations for the documentation.
     transations: &'a TranslationMapSet,
+    /// The languages to look up translations in, in order of preference.
+    langs: Vec<String>,
+    /// The index to resolve documentation links with.
+    links: &'a LinkIndex,
+    /// The assets to resolve images with.
+    assets: &'a BookAssets,
+    /// The commits to list the contributors of the changelog with, if any.
+    commits: Option<&'a CommitLog>,
+    /// The key of the page being generated.
+    page: String,
     /// The output pages.
     pages: Vec<TypstPage>,
+    /// The problems found in the translations.
+    diagnostics: RefCell<Vec<Diagnostic>>,
 }
 
 impl<'a> GenContext<'a> {
-    /// Creates a new context for generating Typst documentation.
-    pub fn new(transations: &'a TranslationMapSet) -> Self {
+    /// Creates a new context for generating Typst documentation, looking up
+    /// translations in the languages `langs` in order of preference.
+    pub fn new(
+        transations: &'a TranslationMapSet,
+        langs: Vec<String>,
+        links: &'a LinkIndex,
+        assets: &'a BookAssets,
+    ) -> Self {
         Self {
             // target: Target::Paged,
             transations,
+            langs,
+            links,
+            assets,
+            commits: None,
+            page: String::new(),
             pages: vec![],
+            diagnostics: RefCell::default(),
         }
     }
 
-    /// Gets the translation for a key.1
+    /// Lists the contributors of the changelog with the commits of `log`.
+    pub fn with_commits(mut self, log: &'a CommitLog) -> Self {
+        self.commits = Some(log);
+        self
+    }
+
+    /// Takes the problems found in the translations so far.
+    pub fn take_diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
+
+    /// Gets the translation for a key, falling back to `fallback` if the key
+    /// is missing.
     fn get_translation<'b: 'a>(&self, key: &str, fallback: &'b str) -> &'a str {
-        let dict = self
-            .transations
-            .get(key)
-            .unwrap_or_else(|| panic!("Missing translation for {key}"));
-        dict.get("zh")
-            .or_else(|| dict.get("en"))
+        let Some(dict) = self.transations.get(key) else {
+            self.report(Diagnostic::error(key, "missing translation"));
+            return fallback;
+        };
+        self.langs
+            .iter()
+            .find_map(|lang| dict.get(lang))
             .map(|s| s.as_str())
             .unwrap_or(fallback)
     }
 
+    /// Converts a Markdown translation with the key `k` on the page `page` to
+    /// Typst, with its first-level headings at `depth`.
+    fn md_to_typst(&self, md: &str, k: &str, page: &str, depth: usize) -> anyhow::Result<String> {
+        let links = self.page_links(page, depth);
+        let mut problems = vec![];
+        let result = md_to_typst_with(md, &links, &mut problems)
+            .with_context(|| format!("Failed to convert {k}"))?;
+        self.report_problems(k, md, problems);
+        Ok(result)
+    }
+
+    /// Converts a title translation with the key `k` to Typst. Links in titles
+    /// are not resolved.
+    fn title_to_typst(&self, title: &str, k: &str) -> anyhow::Result<String> {
+        let mut problems = vec![];
+        let result = md_to_typst_with(title, &(), &mut problems)
+            .with_context(|| format!("Failed to convert {k}"))?;
+        self.report_problems(k, title, problems);
+        Ok(result)
+    }
+
+    /// Converts an HTML translation with the key `k` on the page `page` to
+    /// Typst, with its first-level headings at `depth`.
+    fn html_to_typst(
+        &self,
+        html: &str,
+        k: &str,
+        page: &str,
+        depth: usize,
+    ) -> anyhow::Result<String> {
+        let links = self.page_links(page, depth);
+        let mut problems = vec![];
+        let result = html_to_typst_with(html, &links, &mut problems)
+            .with_context(|| format!("Failed to convert {k}"))?;
+        self.report_problems(k, html, problems);
+        Ok(result)
+    }
+
+    /// Records the problems found in the translation `text` with the key `k`.
+    fn report_problems(&self, k: &str, text: &str, problems: Vec<Problem>) {
+        for problem in problems {
+            self.report(Diagnostic::new(k, text, problem));
+        }
+    }
+
+    /// Records a problem found in the translations.
+    fn report(&self, diagnostic: Diagnostic) {
+        self.diagnostics.borrow_mut().push(diagnostic);
+    }
+
+    /// Gets the resolver for the links, images and headings found on the page
+    /// `page`, nesting first-level headings at `depth`.
+    fn page_links<'b>(&'b self, page: &'b str, depth: usize) -> PageLinks<'b> {
+        PageLinks {
+            index: self.links,
+            assets: self.assets,
+            commits: self.commits,
+            page,
+            depth,
+        }
+    }
+
     /// Gets a page by index.
     pub fn get_page(&self, page: TypstPageIdx) -> &TypstPage {
         self.pages
@@ -80,147 +225,438 @@ impl<'a> GenContext<'a> {
             .unwrap_or_else(|| panic!("Missing page {page:?}"))
     }
 
-    /// Generates a logic page of Typst documentation.
-    pub fn generate_page(&mut self, page: &PageMdModel) -> anyhow::Result<Option<TypstPageIdx>> {
-        let mut children = vec![];
+    /// Generates the logic pages of Typst documentation.
+    pub fn generate_pages(&mut self, pages: &[PageMdModel]) -> anyhow::Result<Vec<TypstPageIdx>> {
+        self.generate_siblings(pages, 1)
+    }
 
-        for child in &page.children {
-            if let Some(page) = self.generate_page(child)? {
-                children.push(page);
-            }
+    /// Writes generated pages to the output.
+    pub fn write_pages(
+        &self,
+        pages: &[TypstPageIdx],
+        result: &mut impl Writer,
+    ) -> anyhow::Result<()> {
+        let mut parts = PartBlocks::default();
+        for page in pages {
+            let page = self.get_page(*page);
+            parts.begin(page, result);
+            page.write(self, result)?;
         }
+        parts.end(result);
+        Ok(())
+    }
 
-        if page.route.contains("changelog") {
-            return Ok(None);
+    /// Generates sibling pages whose titles are headings at `depth`. The pages
+    /// from the beginning of a part on are nested one level below the heading
+    /// of the part, which is placed at `depth`.
+    fn generate_siblings(
+        &mut self,
+        pages: &[PageMdModel],
+        depth: usize,
+    ) -> anyhow::Result<Vec<TypstPageIdx>> {
+        let mut in_part = false;
+        let mut result = vec![];
+        for page in pages {
+            in_part |= page.part.is_some();
+            let depth = depth + usize::from(in_part);
+            if let Some(page) = self.generate_nested_page(page, depth)? {
+                result.push(page);
+            }
         }
+        Ok(result)
+    }
 
-        let k = to_dot_path(&page.route);
-        let k = if k.is_empty() { "index".to_owned() } else { k };
+    /// Generates a page whose title is a heading at `depth`, nesting the
+    /// headings of its children below it.
+    fn generate_nested_page(
+        &mut self,
+        page: &PageMdModel,
+        depth: usize,
+    ) -> anyhow::Result<Option<TypstPageIdx>> {
+        let children = self.generate_siblings(&page.children, depth + 1)?;
+
+        let k = page_key(&page.route);
+        self.page = k.clone();
 
         let title_k = format!("{k}.title");
         let title = self.get_translation(&title_k, &page.title);
         let description_k = format!("{k}.description");
         let description = self.get_translation(&description_k, &page.description);
 
-        // if let Some(part) = page.part {
-        //     translations.push((format!("{part}.part"), part.into()));
-        // }
+        let body = self.generate_body(&page.body, &k, depth + 1)?;
 
-        // check_outline(page.outline, &k, translations);
-        let body = self.generate_body(&page.body, &k)?;
+        let heading = make_heading(&self.title_to_typst(title, &title_k)?, depth, &k);
+        let description = self.md_to_typst(description, &description_k, &k, depth)?;
+
+        let mut seq = vec![];
+        if let Some(part) = &page.part {
+            let part_k = format!("{part}.part");
+            let part = self.get_translation(&part_k, part);
+            let part = self.title_to_typst(part, &part_k)?;
+            seq.push(TypstContent::Typ(make_part(&part, depth - 1, &part_k)));
+        }
 
         let body = vec![
-            TypstContent::Md(title_k, format!("## {title}")),
-            TypstContent::Md(description_k, format!("### {description}")),
+            TypstContent::Seq(seq),
+            TypstContent::Typ(heading),
+            TypstContent::Typ(format!("#docs-description[{}]", description.trim())),
             body,
         ];
         let body = TypstContent::Seq(body);
 
-        let page = TypstPage { children, body };
+        let page = TypstPage {
+            key: k,
+            children,
+            part: page.part.is_some(),
+            body,
+        };
         let page_idx = TypstPageIdx(self.pages.len());
         self.pages.push(page);
 
         Ok(Some(page_idx))
     }
 
-    /// Generates a body of Typst documentation.
-    fn generate_body(&mut self, page: &BodyMdModel, k: &str) -> anyhow::Result<TypstContent> {
+    /// Generates a body of Typst documentation, with item headings at `depth`.
+    fn generate_body(
+        &mut self,
+        page: &BodyMdModel,
+        k: &str,
+        depth: usize,
+    ) -> anyhow::Result<TypstContent> {
         let body = match page {
             BodyMdModel::Html(html) => {
+                // First-level headings are titles, so the sections of a page
+                // start at the second level.
                 let k = format!("{k}.body");
-                self.generate_html(html, &k)?
+                self.generate_html(html, &k, depth - 1)?
             }
-            BodyMdModel::Category(category) => self.generate_category(category, k)?,
-            BodyMdModel::Func(func) => self.generate_func(func, k)?,
-            BodyMdModel::Group(group) => self.generate_group(group, k)?,
-            BodyMdModel::Type(type_) => self.generate_type(type_, k)?,
-            BodyMdModel::Symbols(symbols) => self.generate_symbols(symbols, k)?,
+            BodyMdModel::Category(category) => self.generate_category(category, k, depth)?,
+            BodyMdModel::Func(func) => self.generate_func(func, k, depth)?,
+            BodyMdModel::Group(group) => self.generate_group(group, k, depth)?,
+            BodyMdModel::Type(type_) => self.generate_type(type_, k, depth)?,
+            BodyMdModel::Symbols(symbols) => self.generate_symbols(symbols, k, depth)?,
             BodyMdModel::Packages(html) => {
                 let k = format!("{k}.packages");
-                self.generate_html(html, &k)?
+                self.generate_html(html, &k, depth - 1)?
             }
         };
         Ok(body)
     }
 
-    /// Generates a HTML or Markdown content.
-    fn generate_html(&mut self, html: &HtmlMd, k: &str) -> anyhow::Result<TypstContent> {
+    /// Generates a HTML or Markdown content, with its first-level headings at
+    /// `depth`.
+    fn generate_html(
+        &mut self,
+        html: &HtmlMd,
+        k: &str,
+        depth: usize,
+    ) -> anyhow::Result<TypstContent> {
         match html {
             HtmlMd::Html(html) => {
                 let content = self.get_translation(k, html);
-                Ok(TypstContent::Html(content.to_string()))
+                Ok(TypstContent::Html(
+                    k.to_string(),
+                    content.to_string(),
+                    depth,
+                ))
             }
             HtmlMd::Md(code) => {
                 let content = self.get_translation(k, code);
-                Ok(TypstContent::Md(k.to_string(), content.to_string()))
+                Ok(TypstContent::Md(k.to_string(), content.to_string(), depth))
             }
         }
     }
 
+    /// Generates a HTML or Markdown content that has no translation entry,
+    /// with its first-level headings at `depth`.
+    fn generate_untranslated(&self, html: &HtmlMd, k: &str, depth: usize) -> TypstContent {
+        match html {
+            HtmlMd::Html(html) => TypstContent::Html(k.to_string(), html.to_string(), depth),
+            HtmlMd::Md(code) => TypstContent::Md(k.to_string(), code.to_string(), depth),
+        }
+    }
+
     /// Generates a category content.
     fn generate_category(
         &mut self,
         category: &CategoryMdModel,
         k: &str,
+        depth: usize,
     ) -> anyhow::Result<TypstContent> {
         let k = format!("{k}.{}", category.name);
 
-        let title = self.get_translation(&format!("{k}.title"), &category.title);
-        let heading = TypstContent::Typ(make_heading(title)?);
+        let title_k = format!("{k}.title");
+        let title = self.get_translation(&title_k, &category.title);
+        let heading = TypstContent::Typ(make_heading(
+            &self.title_to_typst(title, &title_k)?,
+            depth,
+            &k,
+        ));
 
         let details_k = format!("{k}.details");
-        let details = self.generate_html(&category.details, &details_k)?;
+        let details = self.generate_html(&category.details, &details_k, depth + 1)?;
+
+        let mut seq = vec![heading, details];
+
+        if !category.items.is_empty() {
+            seq.push(self.generate_category_items(&category.items, depth + 1)?);
+        }
+
+        if let Some(shorthands) = &category.shorthands {
+            let k = format!("{k}.shorthands");
+            if !shorthands.markup.is_empty() {
+                let k = format!("{k}.markup");
+                seq.push(TypstContent::Typ(make_section("markup-shorthands")));
+                seq.push(self.generate_symbol_table(&shorthands.markup, &k, depth + 1)?);
+            }
+            if !shorthands.math.is_empty() {
+                let k = format!("{k}.math");
+                seq.push(TypstContent::Typ(make_section("math-shorthands")));
+                seq.push(self.generate_symbol_table(&shorthands.math, &k, depth + 1)?);
+            }
+        }
 
-        let seq = vec![heading, details];
         Ok(TypstContent::Seq(seq))
     }
 
+    /// Generates the index table of a category, nested at `depth`.
+    fn generate_category_items(
+        &mut self,
+        items: &[CategoryMdItem],
+        depth: usize,
+    ) -> anyhow::Result<TypstContent> {
+        let mut rows = String::new();
+        for item in items {
+            let k = to_dot_path(&item.route);
+            let oneliner_k = format!("{k}.oneliner");
+            let oneliner = self.get_translation(&oneliner_k, &item.oneliner);
+            let oneliner = self.md_to_typst(oneliner, &oneliner_k, &self.page, depth)?;
+            let target = if self.links.page_of(&k).is_some() {
+                format!("label({})", typst_str(&k))
+            } else {
+                "none".to_owned()
+            };
+
+            writeln!(
+                rows,
+                "  (name: {}, code: {}, target: {target}, oneliner: [{}]),",
+                typst_str(&item.name),
+                item.code,
+                oneliner.trim(),
+            )?;
+        }
+
+        Ok(TypstContent::Typ(format!(
+            "#docs-category-items((\n{rows}))"
+        )))
+    }
+
     /// Generates a function content.
-    fn generate_func(&mut self, func: &FuncMdModel, k: &str) -> anyhow::Result<TypstContent> {
+    fn generate_func(
+        &mut self,
+        func: &FuncMdModel,
+        k: &str,
+        depth: usize,
+    ) -> anyhow::Result<TypstContent> {
         let k = format!("{k}.{}", func.name);
-        let title = self.get_translation(&format!("{k}.title"), &func.title);
-        let heading = TypstContent::Typ(make_heading(title)?);
+        let title_k = format!("{k}.title");
+        let title = self.get_translation(&title_k, &func.title);
+        let heading = TypstContent::Typ(make_heading(
+            &self.title_to_typst(title, &title_k)?,
+            depth,
+            &k,
+        ));
+
+        let mut seq = vec![heading];
+
+        if let Some(deprecation) = &func.deprecation {
+            let deprecation_k = format!("{k}.deprecation");
+            let deprecation = self.get_translation(&deprecation_k, deprecation);
+            let deprecation =
+                self.md_to_typst(deprecation, &deprecation_k, &self.page, depth + 1)?;
+            seq.push(TypstContent::Typ(make_deprecation(&deprecation)));
+        }
+
+        let mut badges = vec![];
+        if func.element {
+            badges.push("element");
+        }
+        if func.contextual {
+            badges.push("contextual");
+        }
+        if !badges.is_empty() {
+            seq.push(TypstContent::Typ(format!(
+                "#docs-badges({})",
+                typst_array(badges.iter())
+            )));
+        }
 
         let oneliner_k = format!("{k}.oneliner");
         let oneliner = self.get_translation(&oneliner_k, &func.oneliner);
+        seq.push(TypstContent::Md(
+            oneliner_k,
+            oneliner.to_string(),
+            depth + 1,
+        ));
+
+        let details_k = format!("{k}.details");
+        seq.push(self.generate_html(&func.details, &details_k, depth + 1)?);
+
+        if let Some(example) = &func.example {
+            let example_k = format!("{k}.example");
+            seq.push(self.generate_untranslated(example, &example_k, depth + 1));
+        }
+
+        seq.push(TypstContent::Typ(make_signature(func)));
+
+        if !func.params.is_empty() {
+            seq.push(TypstContent::Typ(make_section("parameters")));
+            for param in &func.params {
+                seq.push(self.generate_param(param, &k, depth + 1)?);
+            }
+        }
+
+        if !func.scope.is_empty() {
+            seq.push(TypstContent::Typ(make_section("definitions")));
+            for scope in &func.scope {
+                seq.push(self.generate_func(scope, &k, depth + 1)?);
+            }
+        }
+
+        Ok(TypstContent::Seq(seq))
+    }
+
+    /// Generates a parameter content.
+    fn generate_param(
+        &mut self,
+        param: &ParamMdModel,
+        k: &str,
+        depth: usize,
+    ) -> anyhow::Result<TypstContent> {
+        let k = format!("{k}.{}", param.name);
+
+        let mut badges = vec![];
+        if param.required {
+            badges.push("required");
+        }
+        if param.positional {
+            badges.push("positional");
+        }
+        if param.variadic {
+            badges.push("variadic");
+        }
+        if param.settable {
+            badges.push("settable");
+        }
 
-        let oneliner = TypstContent::Md(oneliner_k, oneliner.to_string());
+        let heading = format!(
+            "#docs-param({}, key: {}, depth: {depth}, types: {}, badges: {})",
+            typst_str(&param.name),
+            typst_str(&k),
+            typst_array(param.types.iter()),
+            typst_array(badges.iter()),
+        );
+        let mut seq = vec![TypstContent::Typ(heading)];
 
         let details_k = format!("{k}.details");
-        let details = self.generate_html(&func.details, &details_k)?;
+        seq.push(self.generate_html(&param.details, &details_k, depth + 1)?);
+
+        if !param.strings.is_empty() {
+            seq.push(TypstContent::Typ(make_section("strings")));
+            for string in &param.strings {
+                let string_k = format!("{k}.strings.{}", string.string);
+                seq.push(TypstContent::Typ(format!(
+                    "#docs-string({})",
+                    typst_str(&string.string)
+                )));
+                seq.push(self.generate_untranslated(&string.details, &string_k, depth + 1));
+            }
+        }
 
-        let seq = vec![heading, oneliner, details];
+        if let Some(default) = &param.default {
+            let default = match default {
+                HtmlMd::Html(html) => html_text(html),
+                HtmlMd::Md(md) => md.trim().trim_matches('`').to_owned(),
+            };
+            seq.push(TypstContent::Typ(format!(
+                "#docs-default({})",
+                typst_str(&default)
+            )));
+        }
+
+        if let Some(example) = &param.example {
+            let example_k = format!("{k}.example");
+            seq.push(self.generate_untranslated(example, &example_k, depth + 1));
+        }
 
         Ok(TypstContent::Seq(seq))
     }
+
     /// Generates a group content.
-    fn generate_group(&mut self, group: &GroupMdModel, k: &str) -> anyhow::Result<TypstContent> {
+    fn generate_group(
+        &mut self,
+        group: &GroupMdModel,
+        k: &str,
+        depth: usize,
+    ) -> anyhow::Result<TypstContent> {
         let k = format!("{k}.{}", group.name);
-        let title = self.get_translation(&format!("{k}.title"), &group.title);
-        let heading = TypstContent::Typ(make_heading(title)?);
+        let title_k = format!("{k}.title");
+        let title = self.get_translation(&title_k, &group.title);
+        let heading = TypstContent::Typ(make_heading(
+            &self.title_to_typst(title, &title_k)?,
+            depth,
+            &k,
+        ));
 
         let details_k = format!("{k}.details");
-        let details = self.generate_html(&group.details, &details_k)?;
+        let details = self.generate_html(&group.details, &details_k, depth + 1)?;
+
+        let mut seq = vec![heading, details];
+
+        for func in &group.functions {
+            seq.push(self.generate_func(func, &k, depth + 1)?);
+        }
 
-        let seq = vec![heading, details];
         Ok(TypstContent::Seq(seq))
     }
 
     /// Generates a type content.
-    fn generate_type(&mut self, type_: &TypeMdModel, k: &str) -> anyhow::Result<TypstContent> {
+    fn generate_type(
+        &mut self,
+        type_: &TypeMdModel,
+        k: &str,
+        depth: usize,
+    ) -> anyhow::Result<TypstContent> {
         let k = format!("{k}.{}", type_.name);
-        let title = self.get_translation(&format!("{k}.title"), &type_.title);
-        let heading = TypstContent::Typ(make_heading(title)?);
+        let title_k = format!("{k}.title");
+        let title = self.get_translation(&title_k, &type_.title);
+        let heading = TypstContent::Typ(make_heading(
+            &self.title_to_typst(title, &title_k)?,
+            depth,
+            &k,
+        ));
 
         let oneliner_k = format!("{k}.oneliner");
         let oneliner = self.get_translation(&oneliner_k, &type_.oneliner);
 
-        let oneliner = TypstContent::Md(oneliner_k, oneliner.to_string());
+        let oneliner = TypstContent::Md(oneliner_k, oneliner.to_string(), depth + 1);
 
         let details_k = format!("{k}.details");
-        let details = self.generate_html(&type_.details, &details_k)?;
+        let details = self.generate_html(&type_.details, &details_k, depth + 1)?;
 
-        let seq = vec![heading, oneliner, details];
+        let mut seq = vec![heading, oneliner, details];
+
+        if let Some(constructor) = &type_.constructor {
+            seq.push(TypstContent::Typ(make_section("constructor")));
+            seq.push(self.generate_func(constructor, &k, depth + 1)?);
+        }
+
+        if !type_.scope.is_empty() {
+            seq.push(TypstContent::Typ(make_section("definitions")));
+            for scope in &type_.scope {
+                seq.push(self.generate_func(scope, &k, depth + 1)?);
+            }
+        }
 
         Ok(TypstContent::Seq(seq))
     }
@@ -230,25 +666,72 @@ impl<'a> GenContext<'a> {
         &mut self,
         symbols: &SymbolsMdModel,
         k: &str,
+        depth: usize,
     ) -> anyhow::Result<TypstContent> {
         let k = format!("{k}.{}", symbols.name);
-        let title = self.get_translation(&format!("{k}.title"), &symbols.title);
-        let heading = TypstContent::Typ(make_heading(title)?);
+        let title_k = format!("{k}.title");
+        let title = self.get_translation(&title_k, &symbols.title);
+        let heading = TypstContent::Typ(make_heading(
+            &self.title_to_typst(title, &title_k)?,
+            depth,
+            &k,
+        ));
 
         let details_k = format!("{k}.details");
-        let details = self.generate_html(&symbols.details, &details_k)?;
+        let details = self.generate_html(&symbols.details, &details_k, depth + 1)?;
+
+        let table = self.generate_symbol_table(&symbols.list, &k, depth + 1)?;
 
-        let seq = vec![heading, details];
+        let seq = vec![heading, details, table];
         Ok(TypstContent::Seq(seq))
     }
+
+    /// Generates a table of symbols, nested at `depth`.
+    fn generate_symbol_table(
+        &mut self,
+        symbols: &[SymbolMdModel],
+        k: &str,
+        depth: usize,
+    ) -> anyhow::Result<TypstContent> {
+        let mut rows = String::new();
+        for symbol in symbols {
+            let deprecation = match &symbol.deprecation {
+                Some(deprecation) => {
+                    let deprecation_k = format!("{k}.{}.deprecation", symbol.codepoint);
+                    let deprecation = self.get_translation(&deprecation_k, deprecation);
+                    let deprecation =
+                        self.md_to_typst(deprecation, &deprecation_k, &self.page, depth)?;
+                    format!("[{}]", deprecation.trim())
+                }
+                None => "none".to_owned(),
+            };
+
+            let glyph = char::from_u32(symbol.codepoint)
+                .map(String::from)
+                .unwrap_or_default();
+
+            writeln!(
+                rows,
+                "  (glyph: {}, name: {}, alternates: {}, markup: {}, math: {}, class: {}, deprecation: {deprecation}),",
+                typst_str(&glyph),
+                typst_str(&symbol.name),
+                typst_array(symbol.alternates.iter()),
+                typst_option(symbol.markup_shorthand.as_deref()),
+                typst_option(symbol.math_shorthand.as_deref()),
+                typst_option(symbol.math_class.as_deref()),
+            )?;
+        }
+
+        Ok(TypstContent::Typ(format!("#docs-symbols((\n{rows}))")))
+    }
 }
 
 /// Represents the content of a Typst page.
 enum TypstContent {
-    /// HTML content.
-    Html(String),
-    /// Markdown content.
-    Md(String, String),
+    /// HTML content, with its first-level headings at the given depth.
+    Html(String, String, usize),
+    /// Markdown content, with its first-level headings at the given depth.
+    Md(String, String, usize),
     /// Markdown content.
     Typ(String),
     /// A Sequence of content.
@@ -256,22 +739,22 @@ enum TypstContent {
 }
 
 impl TypstContent {
-    /// Writes the content to the output.
-    fn write(&self, result: &mut impl Writer) -> anyhow::Result<()> {
+    /// Writes the content of the page `page` to the output.
+    fn write(&self, ctx: &GenContext, page: &str, result: &mut impl Writer) -> anyhow::Result<()> {
         result.push('\n');
         match self {
-            TypstContent::Html(html) => {
-                write!(result, "```````html\n{html}\n```````\n")?;
+            TypstContent::Html(k, html, depth) => {
+                let t = ctx.html_to_typst(html, k, page, *depth)?;
+                writeln!(result, "{t}")?;
             }
-            TypstContent::Md(prefix, md) => {
-                let _ = prefix;
-                let t = md_to_typst(md)?;
+            TypstContent::Md(k, md, depth) => {
+                let t = ctx.md_to_typst(md, k, page, *depth)?;
                 writeln!(result, "{t}")?;
             }
             TypstContent::Typ(typ) => result.push_str(typ),
             TypstContent::Seq(seq) => {
                 for item in seq {
-                    item.write(result)?;
+                    item.write(ctx, page, result)?;
                 }
             }
         }
@@ -279,6 +762,57 @@ impl TypstContent {
     }
 }
 
+/// Resolves links found on a page against the link index, images against the
+/// assets of the book, and headings against the outline of the page.
+struct PageLinks<'a> {
+    /// The link index.
+    index: &'a LinkIndex,
+    /// The assets of the book.
+    assets: &'a BookAssets,
+    /// The commits to list the contributors with, if any.
+    commits: Option<&'a CommitLog>,
+    /// The key of the page the links are found on.
+    page: &'a str,
+    /// The depth of first-level headings.
+    depth: usize,
+}
+
+impl LinkResolver for PageLinks<'_> {
+    fn resolve_link(&self, link: &str) -> StrResult<Option<String>> {
+        self.index.resolve(link, self.page)
+    }
+
+    fn rewrite_link(&self, link: &str) -> Option<String> {
+        self.index.rewrite(link)
+    }
+
+    fn resolve_image(&self, src: &str) -> StrResult<Option<String>> {
+        self.assets.image(src).map(Some)
+    }
+
+    fn resolve_heading(&self, index: usize, count: usize, id: &str) -> StrResult<Option<String>> {
+        self.index.heading(self.page, index, count, id)
+    }
+
+    fn resolve_example(&self, code: &str) -> StrResult<Option<Vec<String>>> {
+        self.assets.example(code).map(Some)
+    }
+
+    fn resolve_contributors(&self, from: &str, to: &str) -> StrResult<Option<Contributors>> {
+        let Some(log) = self.commits else {
+            return Err("no commits were passed with `--commits`".into());
+        };
+        let commits = log
+            .commits(from, to)
+            .map_err(|err| eco_format!("{err:#}"))?;
+        Ok(Some(contributors(&commits)))
+    }
+
+    fn heading_depth(&self, level: usize) -> usize {
+        self.depth + level - 1
+    }
+}
+
 /// A model for writing output.
 pub trait Writer: fmt::Write {
     /// Writes a string to the output.
@@ -297,8 +831,156 @@ impl Writer for String {
     }
 }
 
-/// Makes a heading.
-fn make_heading(title: &str) -> std::io::Result<String> {
-    let t = md_to_typst(title)?;
-    Ok(format!("#heading(depth: 2)[{}]", t.trim()))
+/// Makes a heading from converted Typst markup, labelled with a dot-path key.
+fn make_heading(title: &str, depth: usize, label: &str) -> String {
+    format!(
+        "#heading(depth: {depth})[{}] #label({})",
+        title.trim(),
+        typst_str(label)
+    )
+}
+
+/// Makes a part divider from converted Typst markup, a title page introducing
+/// the pages of a part. The numbers of the headings in the part leave out the
+/// unnumbered part.
+fn make_part(title: &str, depth: usize, label: &str) -> String {
+    format!(
+        "#set heading(numbering: docs-part-numbering.with({depth}))\n\n#docs-part(key: {}, depth: {depth})[{}]\n\n#pagebreak();\n\n",
+        typst_str(label),
+        title.trim()
+    )
+}
+
+/// Makes a deprecation notice from converted Typst markup.
+fn make_deprecation(message: &str) -> String {
+    format!("#docs-deprecation[{}]", message.trim())
+}
+
+/// Makes a titled section.
+fn make_section(kind: &str) -> String {
+    format!("#docs-section({})", typst_str(kind))
+}
+
+/// Makes the signature block of a function.
+fn make_signature(func: &FuncMdModel) -> String {
+    let params = func
+        .params
+        .iter()
+        .map(|param| {
+            format!(
+                "(name: {}, types: {}, named: {}, variadic: {})",
+                typst_str(&param.name),
+                typst_array(param.types.iter()),
+                param.named,
+                param.variadic,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        "#docs-signature({}, method: {}, params: ({}), returns: {})",
+        typst_str(&func.name),
+        func.self_,
+        params.iter().map(|p| format!("{p},")).collect::<String>(),
+        typst_array(func.returns.iter()),
+    )
+}
+
+/// Makes a Typst array of string literals.
+fn typst_array<T: AsRef<str>>(items: impl Iterator<Item = T>) -> String {
+    let items = items
+        .map(|item| format!("{},", typst_str(item.as_ref())))
+        .collect::<String>();
+    format!("({items})")
+}
+
+/// Makes a Typst string literal or `none`.
+fn typst_option(item: Option<&str>) -> String {
+    match item {
+        Some(item) => typst_str(item),
+        None => "none".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// Makes a page with an empty Markdown body.
+    fn page(route: &str, part: Option<&str>, children: Vec<PageMdModel>) -> PageMdModel {
+        PageMdModel {
+            route: route.into(),
+            title: route.trim_matches('/').into(),
+            description: "".into(),
+            part: part.map(Into::into),
+            outline: vec![],
+            body: BodyMdModel::Html(HtmlMd::Md("".into())),
+            children,
+        }
+    }
+
+    #[test]
+    fn test_parts_in_chapters() {
+        // Like the documentation, the parts begin on children of the
+        // reference.
+        let pages = vec![
+            page("/", None, vec![]),
+            page(
+                "/reference/",
+                None,
+                vec![
+                    page("/reference/syntax/", Some("Language"), vec![]),
+                    page("/reference/styling/", None, vec![]),
+                    page(
+                        "/reference/foundations/",
+                        Some("Library"),
+                        vec![page("/reference/foundations/calc/", None, vec![])],
+                    ),
+                    page("/reference/pdf/", Some("Export"), vec![]),
+                ],
+            ),
+            page("/guides/", None, vec![]),
+        ];
+        let translations = TranslationMapSet::default();
+        let links = LinkIndex::new(&pages);
+        let assets = BookAssets::new("/", Path::new("dist/assets"), Path::new("target"));
+        let mut ctx = GenContext::new(&translations, vec!["en".into()], &links, &assets);
+
+        let typst_pages = ctx.generate_pages(&pages).unwrap();
+        let mut result = String::new();
+        ctx.write_pages(&typst_pages, &mut result).unwrap();
+        let structure = result
+            .lines()
+            .filter(|line| {
+                ["#heading", "#docs-part", "#set", "#[", "]"]
+                    .iter()
+                    .any(|prefix| line.starts_with(prefix))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        insta::assert_snapshot!(structure, @r#"
+        #heading(depth: 1)[] #label("index")
+        #heading(depth: 1)[reference] #label("reference")
+        #[
+        #set heading(numbering: docs-part-numbering.with(2))
+        #docs-part(key: "Language.part", depth: 2)[Language]
+        #heading(depth: 3)[reference/syntax] #label("reference.syntax")
+        #heading(depth: 3)[reference/styling] #label("reference.styling")
+        ]
+        #[
+        #set heading(numbering: docs-part-numbering.with(2))
+        #docs-part(key: "Library.part", depth: 2)[Library]
+        #heading(depth: 3)[reference/foundations] #label("reference.foundations")
+        #heading(depth: 4)[reference/foundations/calc] #label("reference.foundations.calc")
+        ]
+        #[
+        #set heading(numbering: docs-part-numbering.with(2))
+        #docs-part(key: "Export.part", depth: 2)[Export]
+        #heading(depth: 3)[reference/pdf] #label("reference.pdf")
+        ]
+        #heading(depth: 1)[guides] #label("guides")
+        "#);
+    }
 }
@@ -0,0 +1,909 @@
+//! Lowers the HTML used in the documentation to Typst.
+
+use std::io;
+use std::ops::Range;
+
+use pulldown_cmark::escape::StrWrite;
+use typst::diag::StrResult;
+use typst::ecow::{eco_format, EcoString};
+
+use crate::commits::Contributors;
+use crate::convert::{escape_typst, typst_str, LinkResolver};
+use crate::diag::Problem;
+
+/// Converts an HTML document to Typst, collecting the unknown tags into
+/// `problems`.
+pub fn html_to_typst(html: &str, problems: &mut Vec<Problem>) -> io::Result<String> {
+    let (result, unknown) = lower_html(html, HtmlLowering::default())?;
+    problems.extend(unknown);
+    Ok(result)
+}
+
+/// Converts an HTML document to Typst, resolving links and images with
+/// `links` and collecting the unresolvable references and unknown tags into
+/// `problems`. Headings are nested like the Markdown headings of `links`.
+pub fn html_to_typst_with(
+    html: &str,
+    links: &dyn LinkResolver,
+    problems: &mut Vec<Problem>,
+) -> io::Result<String> {
+    let resolved = resolve_html(html, links, problems);
+    let lowering = HtmlLowering::nested(links.heading_depth(1) - 1);
+    let (result, _) = lower_html(&resolved, lowering)?;
+    // The unknown tags are reported at their spans in the original HTML,
+    // which the resolved links may have moved.
+    let (_, unknown) = lower_html(html, HtmlLowering::default())?;
+    problems.extend(unknown);
+    Ok(result)
+}
+
+/// Lowers an HTML document to Typst with `lowering`. Returns the result and
+/// the warnings of the unknown tags.
+fn lower_html(html: &str, mut lowering: HtmlLowering) -> io::Result<(String, Vec<Problem>)> {
+    let mut result = String::new();
+    lowering.lower(&mut result, html)?;
+    let unknown = lowering.finish(&mut result)?;
+    Ok((result, unknown))
+}
+
+/// Rewrites the link destinations and image sources of the tags in an HTML
+/// snippet, collecting the problems. Links into the documentation are
+/// rewritten to `<label>`, and `<contributors>` tags to the lists of the
+/// contributors.
+pub fn resolve_html(html: &str, links: &dyn LinkResolver, problems: &mut Vec<Problem>) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let offset = html.len() - rest.len();
+        let end = tag_end(rest).unwrap_or(rest.len());
+        let tag = &rest[..end];
+        rest = &rest[end..];
+
+        let name = tag[1..]
+            .split(|c: char| c.is_whitespace() || c == '>')
+            .next();
+        let resolved = match name {
+            Some("a") => html_attr_range(tag, "href").map(|range| {
+                let href = &tag[range.clone()];
+                let link = match links.rewrite_link(href) {
+                    Some(link) => Ok(Some(link)),
+                    None => links
+                        .resolve_link(href)
+                        .map(|label| label.map(|label| format!("<{label}>"))),
+                };
+                (range, link)
+            }),
+            // Remote images are not embedded, see `HtmlLowering::start_tag`.
+            Some("img") => html_attr_range(tag, "src")
+                .filter(|range| !is_remote(&tag[range.clone()]))
+                .map(|range| {
+                    let image = links.resolve_image(&tag[range.clone()]);
+                    (range, image)
+                }),
+            // Only sources of a single image are resolved, like the ones of
+            // the examples.
+            Some("source") => html_attr_range(tag, "srcset")
+                .filter(|range| !tag[range.clone()].contains([' ', ',']))
+                .filter(|range| !is_remote(&tag[range.clone()]))
+                .map(|range| {
+                    let image = links.resolve_image(&tag[range.clone()]);
+                    (range, image)
+                }),
+            Some("contributors") => {
+                match resolve_contributors(tag, links) {
+                    Ok(Some(contributors)) => {
+                        if !contributors.unknown.is_empty() {
+                            problems.push(Problem::warning(
+                                Some(offset..offset + tag.len()),
+                                eco_format!(
+                                    "authors without known GitHub accounts are not listed: {}",
+                                    contributors.unknown.join(", ")
+                                ),
+                            ));
+                        }
+                        result.push_str(&contributors.html);
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        problems.push(Problem::warning(
+                            Some(offset..offset + tag.len()),
+                            eco_format!("contributors are not listed ({err})"),
+                        ));
+                        result.push_str(tag);
+                    }
+                }
+                continue;
+            }
+            _ => None,
+        };
+
+        match resolved {
+            Some((range, Ok(Some(value)))) => {
+                result.push_str(&tag[..range.start]);
+                result.push_str(&value);
+                result.push_str(&tag[range.end..]);
+            }
+            Some((range, Err(err))) => {
+                let message =
+                    eco_format!("unresolvable reference: {} ({err})", &tag[range.clone()]);
+                problems.push(Problem::error(
+                    range.start + offset..range.end + offset,
+                    message,
+                ));
+                result.push_str(tag);
+            }
+            _ => result.push_str(tag),
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Resolves a `<contributors from=".." to=".." />` tag to the list of the
+/// contributors between the two tags.
+fn resolve_contributors(tag: &str, links: &dyn LinkResolver) -> StrResult<Option<Contributors>> {
+    let attr = |name: &str| html_attr_range(tag, name).map(|range| &tag[range]);
+    match (attr("from"), attr("to")) {
+        (Some(from), Some(to)) => links.resolve_contributors(from, to),
+        _ => Err("expected the attributes `from` and `to`".into()),
+    }
+}
+
+/// Gets the text of an HTML snippet, e.g. of highlighted code, leaving out
+/// the tags.
+pub fn html_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find('>')
+            .map_or("", |end| &rest[start + end + 1..]);
+    }
+    text.push_str(rest);
+    unescape_html(&text)
+}
+
+/// Decodes the character references in an HTML text.
+pub fn unescape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let ch = match &rest[1..end] {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = entity.strip_prefix('#')?;
+                    let code = match code.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => code.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((ch, end))
+        });
+
+        match decoded {
+            Some((ch, end)) => {
+                result.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Finds the range of the value of an attribute in the first tag of an HTML
+/// snippet.
+pub fn html_attr_range(html: &str, attr: &str) -> Option<Range<usize>> {
+    let end = tag_end(html).map_or(html.len(), |end| end - 1);
+    let tag = &html[..end];
+    let mut offset = 0;
+    while let Some(pos) = tag[offset..].find(attr) {
+        let start = offset + pos;
+        offset = start + attr.len();
+
+        let preceded = tag[..start].ends_with(char::is_whitespace);
+        let rest = tag[offset..].trim_start();
+        if !preceded || !rest.starts_with('=') {
+            continue;
+        }
+
+        // The value is a suffix of the tag.
+        let value = rest[1..].trim_start();
+        let value_start = tag.len() - value.len();
+        let quote = value.chars().next()?;
+        return if quote == '"' || quote == '\'' {
+            let len = value[1..].find(quote)?;
+            Some(value_start + 1..value_start + 1 + len)
+        } else {
+            let len = value
+                .find(|c: char| c.is_whitespace() || c == '/')
+                .unwrap_or(value.len());
+            Some(value_start..value_start + len)
+        };
+    }
+    None
+}
+
+/// An HTML element being lowered.
+#[derive(Debug)]
+struct Element {
+    /// The name of the tag.
+    name: EcoString,
+    /// How the element is closed.
+    close: Close,
+}
+
+/// How an element is closed.
+#[derive(Debug)]
+enum Close {
+    /// Writes the given Typst code.
+    Write(&'static str),
+    /// Writes the captured text as a raw element.
+    Raw {
+        /// Whether the raw element is a block.
+        block: bool,
+        /// The language of the raw element.
+        lang: Option<&'static str>,
+    },
+    /// Writes nothing.
+    Transparent,
+}
+
+/// The context an element's children are written in.
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    /// Typst markup.
+    Markup,
+    /// Typst code, e.g. between the cells of a table.
+    Code,
+}
+
+/// Lowers a stream of HTML snippets to Typst. The snippets may be interleaved
+/// with Markdown text, which is written with [`HtmlLowering::text`].
+#[derive(Debug, Default)]
+pub struct HtmlLowering {
+    /// The open elements.
+    stack: Vec<Element>,
+    /// The text captured for a raw element.
+    raw: Option<String>,
+    /// The unfinished tag or comment at the end of the last snippet.
+    pending: String,
+    /// The unknown tags and their spans, each reported once.
+    unknown: Vec<(EcoString, Range<usize>)>,
+    /// The number of levels the headings are nested by.
+    offset: usize,
+    /// The vector image of the open `<picture>`, preferred to its `<img>`.
+    svg_source: Option<String>,
+}
+
+impl HtmlLowering {
+    /// Creates a lowering nesting the headings by `offset` levels, e.g. `<h1>`
+    /// is lowered to a second-level heading with an offset of one.
+    pub fn nested(offset: usize) -> Self {
+        Self {
+            offset,
+            ..Self::default()
+        }
+    }
+
+    /// Lowers an HTML snippet.
+    pub fn lower(&mut self, w: &mut impl StrWrite, html: &str) -> io::Result<()> {
+        self.lower_at(w, html, 0)
+    }
+
+    /// Lowers an HTML snippet found at `offset` in a document, reporting the
+    /// unknown tags at their spans in the document.
+    pub fn lower_at(&mut self, w: &mut impl StrWrite, html: &str, offset: usize) -> io::Result<()> {
+        let origin = offset.saturating_sub(self.pending.len());
+        let mut buf = std::mem::take(&mut self.pending);
+        buf.push_str(html);
+
+        let mut rest = buf.as_str();
+        while !rest.is_empty() {
+            let Some(start) = rest.find('<') else {
+                self.html_text(w, rest)?;
+                break;
+            };
+
+            self.html_text(w, &rest[..start])?;
+            rest = &rest[start..];
+
+            let end = if rest.starts_with("<!--") {
+                rest.find("-->").map(|end| end + 3)
+            } else {
+                tag_end(rest)
+            };
+            let Some(end) = end else {
+                self.pending = rest.to_owned();
+                break;
+            };
+
+            let tag = &rest[..end];
+            let tag_start = origin + buf.len() - rest.len();
+            rest = &rest[end..];
+            if tag.starts_with("<!") {
+                continue;
+            }
+
+            if let Some(name) = tag.strip_prefix("</") {
+                self.end_tag(w, name.trim_end_matches('>').trim())?;
+            } else {
+                self.start_tag(w, tag, tag_start..tag_start + end)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a text that is not HTML-encoded, which starts a line of markup
+    /// if `line_start` is set.
+    pub fn text(&mut self, w: &mut impl StrWrite, text: &str, line_start: bool) -> io::Result<()> {
+        if let Some(raw) = &mut self.raw {
+            raw.push_str(text);
+            return Ok(());
+        }
+
+        match self.mode() {
+            Mode::Markup => escape_typst(w, text, line_start),
+            // Only whitespace separates the cells of a table.
+            Mode::Code => Ok(()),
+        }
+    }
+
+    /// Closes the elements left open, and returns the warnings of the unknown
+    /// tags.
+    pub fn finish(mut self, w: &mut impl StrWrite) -> io::Result<Vec<Problem>> {
+        while let Some(elem) = self.stack.pop() {
+            self.close(w, elem)?;
+        }
+        Ok(self
+            .unknown
+            .into_iter()
+            .map(|(tag, span)| {
+                Problem::warning(Some(span), eco_format!("unknown HTML tag <{tag}>"))
+            })
+            .collect())
+    }
+
+    /// Writes an HTML-encoded text.
+    fn html_text(&mut self, w: &mut impl StrWrite, text: &str) -> io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.text(w, &unescape_html(text), true)
+    }
+
+    /// Gets the context the next children are written in.
+    fn mode(&self) -> Mode {
+        self.stack
+            .iter()
+            .rev()
+            .find_map(|elem| match elem.name.as_str() {
+                "table" | "thead" | "tbody" | "tfoot" | "tr" => Some(Mode::Code),
+                "div.previewed-code" => Some(Mode::Code),
+                "th" | "td" | "div.preview" => Some(Mode::Markup),
+                _ => None,
+            })
+            .unwrap_or(Mode::Markup)
+    }
+
+    /// Lowers a start tag at `span`, e.g. `<img src="a.png" />`.
+    fn start_tag(
+        &mut self,
+        w: &mut impl StrWrite,
+        tag: &str,
+        span: Range<usize>,
+    ) -> io::Result<()> {
+        let body = tag
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .trim_end_matches('/');
+        let name_end = body.find(|c: char| c.is_whitespace()).unwrap_or(body.len());
+        let name = body[..name_end].to_ascii_lowercase();
+        let attr = |attr: &str| html_attr_range(tag, attr).map(|range| unescape_html(&tag[range]));
+
+        // Tags in raw elements only carry highlighting.
+        if self.raw.is_some() {
+            if !is_void(&name) {
+                self.push(&name, Close::Transparent);
+            }
+            return Ok(());
+        }
+
+        let close = match name.as_str() {
+            "br" => return w.write_str("#linebreak();"),
+            "hr" => return w.write_str("#line(length: 100%);"),
+            "img" => {
+                let src = self
+                    .svg_source
+                    .take()
+                    .or_else(|| attr("src"))
+                    .unwrap_or_default();
+                // Typst cannot load remote images, e.g. the avatars of the
+                // contributors, so their descriptions are written instead.
+                if is_remote(&src) {
+                    let text = attr("title").or_else(|| attr("alt")).unwrap_or_default();
+                    return self.text(w, &text, true);
+                }
+                return match attr("alt") {
+                    Some(alt) => w.write_fmt(format_args!(
+                        "#image({}, alt: {});",
+                        typst_str(&src),
+                        typst_str(&alt)
+                    )),
+                    None => w.write_fmt(format_args!("#image({});", typst_str(&src))),
+                };
+            }
+            "p" => {
+                w.write_str("\n\n")?;
+                Close::Write("\n")
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap();
+                w.write_fmt(format_args!("#heading(depth: {})[", level + self.offset))?;
+                Close::Write("];")
+            }
+            "a" => match attr("href") {
+                Some(href) => {
+                    // Resolved labels are written as `<label>` by the handler.
+                    match href.strip_prefix('<').and_then(|h| h.strip_suffix('>')) {
+                        Some(label) => {
+                            w.write_fmt(format_args!("#link(label({}))[", typst_str(label)))?
+                        }
+                        None => w.write_fmt(format_args!("#link({})[", typst_str(&href)))?,
+                    }
+                    Close::Write("];")
+                }
+                None => Close::Transparent,
+            },
+            "strong" | "b" => open(w, "#strong[")?,
+            "em" | "i" => open(w, "#emph[")?,
+            "s" | "del" => open(w, "#strike[")?,
+            "sup" => open(w, "#super[")?,
+            "sub" => open(w, "#sub[")?,
+            "blockquote" => open(w, "#quote(block: true)[")?,
+            "kbd" => open(w, "#docs-kbd[")?,
+            "details" => open(w, "#docs-details[")?,
+            "summary" => open(w, "#docs-summary[")?,
+            "ul" if attr("class").as_deref() == Some("contribs") => open(w, "#docs-contributors[")?,
+            "ul" | "ol" => Close::Transparent,
+            // The book uses the fallback image of a picture.
+            "picture" => {
+                self.svg_source = None;
+                Close::Transparent
+            }
+            "source" => {
+                let in_picture = self.stack.last().is_some_and(|elem| elem.name == "picture");
+                if in_picture
+                    && self.svg_source.is_none()
+                    && attr("type").as_deref() == Some("image/svg+xml")
+                {
+                    self.svg_source = attr("srcset");
+                }
+                return Ok(());
+            }
+            "li" => {
+                let ordered = self
+                    .stack
+                    .iter()
+                    .rev()
+                    .find(|elem| elem.name == "ul" || elem.name == "ol")
+                    .is_some_and(|elem| elem.name == "ol");
+                open(
+                    w,
+                    if ordered {
+                        "#enum.item["
+                    } else {
+                        "#list.item["
+                    },
+                )?
+            }
+            "code" => {
+                self.raw = Some(String::new());
+                Close::Raw {
+                    block: false,
+                    lang: None,
+                }
+            }
+            "pre" => {
+                self.raw = Some(String::new());
+                let previewed = self
+                    .stack
+                    .iter()
+                    .any(|elem| elem.name == "div.previewed-code");
+                let lang = previewed.then_some("typ");
+                Close::Raw { block: true, lang }
+            }
+            "span" => Close::Transparent,
+            // Unresolved contributors are reported by `resolve_html`.
+            "contributors" => Close::Transparent,
+            "div" => match attr("class").as_deref() {
+                Some("info-box") => open(w, "#docs-info-box[")?,
+                // The source and the preview of an example are laid out side
+                // by side, passed as the arguments of `docs-example`.
+                Some("previewed-code") => {
+                    w.write_str("#docs-example(")?;
+                    self.push("div.previewed-code", Close::Write(");"));
+                    return Ok(());
+                }
+                Some("preview") => {
+                    let close = match self.mode() {
+                        Mode::Markup => open(w, "#docs-preview[")?,
+                        Mode::Code => {
+                            w.write_str("docs-preview[")?;
+                            Close::Write("],")
+                        }
+                    };
+                    self.push("div.preview", close);
+                    return Ok(());
+                }
+                None => Close::Transparent,
+                Some(class) => {
+                    self.report(eco_format!("div.{class}"), span);
+                    Close::Transparent
+                }
+            },
+            "table" => {
+                w.write_str("#docs-html-table(")?;
+                Close::Write(");")
+            }
+            "thead" => {
+                w.write_str("header: (")?;
+                Close::Write("),")
+            }
+            "tbody" | "tfoot" => Close::Transparent,
+            "tr" => {
+                w.write_str("(")?;
+                Close::Write("),")
+            }
+            "th" | "td" => {
+                w.write_str("[")?;
+                Close::Write("],")
+            }
+            _ => {
+                self.report(name.as_str().into(), span);
+                Close::Transparent
+            }
+        };
+
+        if !is_void(&name) && !tag.ends_with("/>") {
+            self.push(&name, close);
+        } else {
+            // A self-closing element has no children.
+            self.close(
+                w,
+                Element {
+                    name: name.into(),
+                    close,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Lowers an end tag, closing the elements left open in it.
+    fn end_tag(&mut self, w: &mut impl StrWrite, name: &str) -> io::Result<()> {
+        let name = name.to_ascii_lowercase();
+        // Elements with a class are named like `div.previewed-code`.
+        let matches = |elem: &Element| elem.name.split('.').next() == Some(name.as_str());
+        if !self.stack.iter().any(matches) {
+            return Ok(());
+        }
+
+        while let Some(elem) = self.stack.pop() {
+            let found = matches(&elem);
+            self.close(w, elem)?;
+            if found {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the closing of an element.
+    fn close(&mut self, w: &mut impl StrWrite, elem: Element) -> io::Result<()> {
+        match elem.close {
+            Close::Write(close) => w.write_str(close),
+            Close::Raw { block, lang } => {
+                let text = self.raw.take().unwrap_or_default();
+                let (text, args) = if block {
+                    let text = text.strip_prefix('\n').unwrap_or(&text).to_owned();
+                    let lang = lang.map(|lang| format!(", lang: {}", typst_str(lang)));
+                    (text, format!(", block: true{}", lang.unwrap_or_default()))
+                } else {
+                    (text, String::new())
+                };
+
+                match self.mode() {
+                    Mode::Markup => w.write_fmt(format_args!("#raw({}{args});", typst_str(&text))),
+                    Mode::Code => w.write_fmt(format_args!("raw({}{args}),", typst_str(&text))),
+                }
+            }
+            Close::Transparent => Ok(()),
+        }
+    }
+
+    /// Pushes an open element.
+    fn push(&mut self, name: &str, close: Close) {
+        self.stack.push(Element {
+            name: name.into(),
+            close,
+        });
+    }
+
+    /// Reports an unknown tag found at `span`.
+    fn report(&mut self, tag: EcoString, span: Range<usize>) {
+        if !self.unknown.iter().any(|(known, _)| *known == tag) {
+            self.unknown.push((tag, span));
+        }
+    }
+}
+
+/// Finds the end of the tag at the start of an HTML snippet, skipping quoted
+/// attribute values.
+fn tag_end(html: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Writes the opening of an element whose content is written in a content
+/// block.
+fn open(w: &mut impl StrWrite, open: &str) -> io::Result<Close> {
+    w.write_str(open)?;
+    Ok(Close::Write("];"))
+}
+
+/// Whether an image is loaded from the web.
+fn is_remote(src: &str) -> bool {
+    src.starts_with("https://") || src.starts_with("http://")
+}
+
+/// Whether an HTML element never has children.
+fn is_void(name: &str) -> bool {
+    matches!(
+        name,
+        "area"
+            | "br"
+            | "col"
+            | "embed"
+            | "hr"
+            | "img"
+            | "input"
+            | "link"
+            | "meta"
+            | "source"
+            | "wbr"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::diag::StrResult;
+
+    use super::*;
+
+    /// Converts an HTML document to Typst, asserting that all tags are known.
+    fn lower(html: &str) -> String {
+        let mut problems = vec![];
+        let result = html_to_typst(html, &mut problems).unwrap();
+        assert_eq!(problems, vec![]);
+        result
+    }
+
+    #[test]
+    fn test_html_to_typst() {
+        assert_eq!(
+            lower(r#"Press <kbd>Ctrl</kbd> + <kbd>S</kbd>.<br>Done &amp; saved"#),
+            "Press #docs-kbd[Ctrl]; \\+ #docs-kbd[S];.#linebreak();Done & saved"
+        );
+        assert_eq!(
+            lower(r#"<div class="info-box">Use <code>#set</code> [here]</div>"#),
+            r##"#docs-info-box[Use #raw("#set"); \[here\]];"##
+        );
+        assert_eq!(
+            lower(concat!(
+                r#"<div class="previewed-code"><pre><span class="typ-key">#let</span> x = &quot;a&quot;</pre>"#,
+                r#"<div class="preview"><img src="/assets/1.png" alt="Preview"></div></div>"#,
+            )),
+            concat!(
+                r##"#docs-example(raw("#let x = \"a\"", block: true, lang: "typ"),"##,
+                r#"docs-preview[#image("/assets/1.png", alt: "Preview");],);"#,
+            )
+        );
+        assert_eq!(
+            lower(concat!(
+                r#"<div class="preview"><picture><source srcset="/assets/1.svg" type="image/svg+xml">"#,
+                r#"<img src="/assets/1.png" alt="Preview"></picture></div>"#,
+            )),
+            r#"#docs-preview[#image("/assets/1.svg", alt: "Preview");];"#
+        );
+        assert_eq!(
+            lower(concat!(
+                r#"<ul class="contribs"><li><a href="https://github.com/octo" target="_blank">"#,
+                r#"<img src="https://a/1?s=64&amp;v=4" alt="GitHub avatar of octo" title="@octo made 2 contributions">"#,
+                r#"</a></li></ul>"#,
+            )),
+            r#"#docs-contributors[#list.item[#link("https://github.com/octo")[\@octo made 2 contributions];];];"#
+        );
+        assert_eq!(
+            lower("<details><summary>More</summary>\n<p>Text</p></details>"),
+            "#docs-details[#docs-summary[More];\n\n\nText\n];"
+        );
+    }
+
+    #[test]
+    fn test_html_to_typst_with_resolver() {
+        /// Moves the generated assets into the book.
+        struct Assets;
+
+        impl LinkResolver for Assets {
+            fn resolve_link(&self, link: &str) -> StrResult<Option<String>> {
+                Ok(link.strip_prefix('$').map(str::to_owned))
+            }
+
+            fn resolve_image(&self, src: &str) -> StrResult<Option<String>> {
+                match src.strip_prefix("/docs/assets/") {
+                    Some("missing.png") => Err("missing asset".into()),
+                    Some(name) => Ok(Some(format!("assets/{name}"))),
+                    None => Ok(None),
+                }
+            }
+
+            fn heading_depth(&self, level: usize) -> usize {
+                level + 2
+            }
+        }
+
+        let html = concat!(
+            r#"<div class="previewed-code"><pre>= Hi</pre>"#,
+            r#"<div class="preview"><img src="/docs/assets/1f.png" alt="Preview"></div></div>"#,
+            r#"<a href="$guides">Guides</a>"#,
+            r#"<h2>Tables</h2>"#,
+            r#"<picture><source srcset="/docs/assets/2f.svg" type="image/svg+xml">"#,
+            r#"<img src="/docs/assets/2f.png" alt="Preview"></picture>"#,
+        );
+        let mut problems = vec![];
+        assert_eq!(
+            html_to_typst_with(html, &Assets, &mut problems).unwrap(),
+            concat!(
+                r#"#docs-example(raw("= Hi", block: true, lang: "typ"),"#,
+                r#"docs-preview[#image("assets/1f.png", alt: "Preview");],);"#,
+                r#"#link(label("guides"))[Guides];"#,
+                r#"#heading(depth: 4)[Tables];"#,
+                r#"#image("assets/2f.svg", alt: "Preview");"#,
+            )
+        );
+
+        assert_eq!(problems, vec![]);
+
+        html_to_typst_with(
+            r#"Tiger: <img src="/docs/assets/missing.png">"#,
+            &Assets,
+            &mut problems,
+        )
+        .unwrap();
+        assert_eq!(
+            problems,
+            vec![Problem::error(
+                17..41,
+                "unresolvable reference: /docs/assets/missing.png (missing asset)"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_html_contributors() {
+        /// Lists the contributors of a single release.
+        struct Commits;
+
+        impl LinkResolver for Commits {
+            fn resolve_link(&self, _link: &str) -> StrResult<Option<String>> {
+                Ok(None)
+            }
+
+            fn resolve_contributors(
+                &self,
+                from: &str,
+                to: &str,
+            ) -> StrResult<Option<Contributors>> {
+                match (from, to) {
+                    ("v0.1.0", "v0.2.0") => Ok(Some(Contributors {
+                        html: r#"<ul class="contribs"><li><a href="https://github.com/octo">octo</a></li></ul>"#
+                            .into(),
+                        unknown: vec!["Bob".into(), "Jane Doe".into()],
+                    })),
+                    _ => Err(eco_format!("unknown range {from}..{to}")),
+                }
+            }
+        }
+
+        let html = concat!(
+            r#"<contributors from="v0.1.0" to="v0.2.0" />"#,
+            r#"<contributors from="v0.2.0" to="v0.3.0" />"#,
+        );
+        let mut problems = vec![];
+        assert_eq!(
+            html_to_typst_with(html, &Commits, &mut problems).unwrap(),
+            r#"#docs-contributors[#list.item[#link("https://github.com/octo")[octo];];];"#
+        );
+        assert_eq!(
+            problems,
+            vec![
+                Problem::warning(
+                    Some(0..42),
+                    "authors without known GitHub accounts are not listed: Bob, Jane Doe"
+                ),
+                Problem::warning(
+                    Some(42..84),
+                    "contributors are not listed (unknown range v0.2.0..v0.3.0)"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_html_table_to_typst() {
+        let html = "<table>\n<thead><tr><th>Name</th><th>Use</th></tr></thead>\n\
+                    <tbody><tr><td><code>a</code></td><td>[x]</td></tr></tbody>\n</table>";
+        assert_eq!(
+            lower(html),
+            r#"#docs-html-table(header: (([Name],[Use],),),([#raw("a");],[\[x\]],),);"#
+        );
+    }
+
+    #[test]
+    fn test_html_unknown_tags() {
+        let mut problems = vec![];
+        let result = html_to_typst(
+            r#"<video src="a.mp4">x</video><div class="tip">y</div><video></video>"#,
+            &mut problems,
+        )
+        .unwrap();
+        assert_eq!(result, "xy");
+        assert_eq!(
+            problems,
+            vec![
+                Problem::warning(Some(0..19), "unknown HTML tag <video>"),
+                Problem::warning(Some(28..45), "unknown HTML tag <div.tip>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_html_attr_range() {
+        let html = r#"<img alt="A" src='b.png' width=3>"#;
+        assert_eq!(&html[html_attr_range(html, "src").unwrap()], "b.png");
+        assert_eq!(&html[html_attr_range(html, "width").unwrap()], "3");
+        assert_eq!(html_attr_range(html, "href"), None);
+        assert_eq!(
+            unescape_html("&lt;a&gt; &#39;&#x41;&unknown; &"),
+            "<a> 'A&unknown; &"
+        );
+    }
+}
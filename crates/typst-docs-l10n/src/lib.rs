@@ -7,8 +7,15 @@ use typst_docs::{
     SymbolModel, SymbolsModel, TypeModel,
 };
 
+pub mod assets;
+pub mod cache;
+pub mod commits;
 pub mod convert;
+pub mod diag;
+pub mod filter;
 pub mod generate;
+pub mod html;
+pub mod link;
 pub mod markdown;
 pub mod resolve;
 pub mod translate;
@@ -432,3 +439,13 @@ impl From<SymbolModel> for SymbolMdModel {
 fn to_dot_path(path: &str) -> String {
     path.trim_matches('/').replace("/", ".")
 }
+
+/// Convert a page route to the dot path keying the page.
+fn page_key(route: &str) -> String {
+    let k = to_dot_path(route);
+    if k.is_empty() {
+        "index".to_owned()
+    } else {
+        k
+    }
+}
@@ -0,0 +1,656 @@
+//! Resolves documentation links against the page tree.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+use typst::diag::StrResult;
+use typst::ecow::eco_format;
+
+use crate::*;
+
+/// An index of the labels of the generated headings, used to resolve
+/// `$`-prefixed links into cross-references.
+#[derive(Debug, Default)]
+pub struct LinkIndex {
+    /// The base URL of the documentation.
+    base: String,
+    /// The keys of the pages labels are attached on, keyed by label.
+    labels: HashMap<String, String>,
+    /// The labels of items, keyed by the dotted name of the item, e.g.
+    /// `calc.max`.
+    definitions: HashMap<String, String>,
+    /// The labels of anchors, keyed by `{page}#{anchor}`.
+    anchors: HashMap<String, String>,
+    /// The first anchors of labels on their pages, keyed by label.
+    label_anchors: HashMap<String, String>,
+    /// The IDs of the headings in the outlines of Markdown pages, in order,
+    /// keyed by page.
+    outlines: HashMap<String, Vec<String>>,
+    /// The links rewritten before they are resolved.
+    map: LinkMap,
+    /// The routes of the pages, keyed by page.
+    routes: HashMap<String, String>,
+    /// The pages left out of the book.
+    excluded: HashSet<String>,
+}
+
+/// The online documentation, linked to for the pages left out of the book.
+const ONLINE_DOCS: &str = "https://typst.app/docs/";
+
+/// Rewrites links, e.g. from the upstream documentation to a localized mirror.
+/// The map is loaded from a TOML file:
+///
+/// ```toml
+/// [links]
+/// "https://typst.app/docs/" = "https://typst-doc-cn.github.io/docs/"
+/// ```
+///
+/// A link starting with a key has the key replaced by its value, trying the
+/// longest key first.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct LinkMap {
+    /// The replacements of the link prefixes.
+    #[serde(default)]
+    links: BTreeMap<String, String>,
+}
+
+impl LinkMap {
+    /// Loads a link map from a TOML file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read the link map {}", path.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("failed to parse the link map {}", path.display()))
+    }
+
+    /// Rewrites a link. Returns `None` if no prefix of the link is mapped.
+    pub fn rewrite(&self, link: &str) -> Option<String> {
+        self.links
+            .iter()
+            .filter(|(prefix, _)| link.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, target)| format!("{target}{}", &link[prefix.len()..]))
+    }
+}
+
+impl LinkIndex {
+    /// Creates an index of the given pages. The route of the first page, the
+    /// overview, is taken as the base URL.
+    pub fn new(pages: &[PageMdModel]) -> Self {
+        let base = pages
+            .first()
+            .map(|page| page.route.to_string())
+            .unwrap_or_else(|| "/".to_owned());
+
+        let mut index = Self {
+            base,
+            ..Self::default()
+        };
+        for page in pages {
+            index.add_page(page);
+        }
+        index
+    }
+
+    /// Rewrites the links matched by `map` before resolving them.
+    pub fn with_map(mut self, map: LinkMap) -> Self {
+        self.map = map;
+        self
+    }
+
+    /// Leaves the pages of the index that are not in `pages` out of the book.
+    /// Links to them are rewritten to the online documentation.
+    pub fn with_pages(mut self, pages: &[PageMdModel]) -> Self {
+        /// Collects the keys of pages and their children.
+        fn collect(pages: &[PageMdModel], kept: &mut HashSet<String>) {
+            for page in pages {
+                kept.insert(page_key(&page.route));
+                collect(&page.children, kept);
+            }
+        }
+
+        let mut kept = HashSet::new();
+        collect(pages, &mut kept);
+        self.excluded = self
+            .routes
+            .keys()
+            .filter(|k| !kept.contains(*k))
+            .cloned()
+            .collect();
+        self
+    }
+
+    /// Rewrites a link with the link map of the index, see [`LinkMap`]. Links
+    /// into pages left out of the book are rewritten to the online
+    /// documentation, which the link map may rewrite too.
+    pub fn rewrite(&self, link: &str) -> Option<String> {
+        if let Some(link) = self.map.rewrite(link) {
+            return Some(link);
+        }
+        if self.excluded.is_empty() || !link.starts_with('$') {
+            return None;
+        }
+
+        let label = self.resolve(link, "").ok()??;
+        let page = self.labels.get(&label)?;
+        if !self.excluded.contains(page) {
+            return None;
+        }
+
+        let route = &self.routes[page];
+        let mut url = format!(
+            "{ONLINE_DOCS}{}",
+            route.strip_prefix(&self.base).unwrap_or(route)
+        );
+        if let Some(anchor) = self.label_anchors.get(&label) {
+            url.push('#');
+            url.push_str(anchor);
+        }
+        Some(self.map.rewrite(&url).unwrap_or(url))
+    }
+
+    /// Gets the key of the page a label is attached on.
+    pub fn page_of(&self, label: &str) -> Option<&str> {
+        self.labels.get(label).map(String::as_str)
+    }
+
+    /// Adds a page and its children to the index.
+    fn add_page(&mut self, page: &PageMdModel) {
+        let k = page_key(&page.route);
+        self.add_label(&k, &k);
+        self.routes.insert(k.clone(), page.route.to_string());
+
+        match &page.body {
+            BodyMdModel::Category(category) => {
+                self.add_item(&k, &k, &category.name, &category.name);
+            }
+            BodyMdModel::Func(func) => {
+                self.add_func(func, &k, &k, &qualified_name(func), None);
+            }
+            BodyMdModel::Group(group) => {
+                let label = self.add_item(&k, &k, &group.name, &group.name);
+                for func in &group.functions {
+                    let name = format!("{}.{}", group.name, func.name);
+                    let anchor = format!("functions-{}", func.name);
+                    self.add_func(func, &k, &label, &name, Some(&anchor));
+                }
+            }
+            BodyMdModel::Type(type_) => {
+                let label = self.add_item(&k, &k, &type_.name, &type_.name);
+                if let Some(constructor) = &type_.constructor {
+                    self.add_func(constructor, &k, &label, &type_.name, Some("constructor"));
+                }
+                for func in &type_.scope {
+                    let name = format!("{}.{}", type_.name, func.name);
+                    let anchor = format!("definitions-{}", func.name);
+                    self.add_func(func, &k, &label, &name, Some(&anchor));
+                }
+            }
+            BodyMdModel::Symbols(symbols) => {
+                self.add_item(&k, &k, &symbols.name, &symbols.name);
+            }
+            BodyMdModel::Html(_) => {
+                let mut ids = vec![];
+                self.add_outline(&k, &page.outline, &mut ids);
+                self.outlines.insert(k.clone(), ids);
+            }
+            BodyMdModel::Packages(_) => {}
+        }
+
+        // The remaining anchors of the outline, e.g. `parameters`, link to
+        // the page itself.
+        if !matches!(page.body, BodyMdModel::Html(_)) {
+            self.add_outline_anchors(&k, &page.outline);
+        }
+
+        for child in &page.children {
+            self.add_page(child);
+        }
+    }
+
+    /// Adds the headings of an outline, labelled as `{page}.{id}`.
+    fn add_outline(&mut self, page: &str, outline: &[OutlineMdItem], ids: &mut Vec<String>) {
+        for item in outline {
+            let label = format!("{page}.{}", item.id);
+            self.add_label(&label, page);
+            self.add_anchor(page, &item.id, &label);
+            ids.push(item.id.to_string());
+            self.add_outline(page, &item.children, ids);
+        }
+    }
+
+    /// Adds the anchors of an outline without labelled headings, linking to
+    /// the page.
+    fn add_outline_anchors(&mut self, page: &str, outline: &[OutlineMdItem]) {
+        for item in outline {
+            self.add_anchor(page, &item.id, page);
+            self.add_outline_anchors(page, &item.children);
+        }
+    }
+
+    /// Adds a function, its parameters and its scope. The function is labelled
+    /// as `{parent}.{func.name}` and defined as `name`.
+    fn add_func(
+        &mut self,
+        func: &FuncMdModel,
+        page: &str,
+        parent: &str,
+        name: &str,
+        anchor: Option<&str>,
+    ) {
+        let label = self.add_item(page, parent, &func.name, name);
+        if let Some(anchor) = anchor {
+            self.add_anchor(page, anchor, &label);
+        }
+
+        for param in &func.params {
+            let param_label = format!("{label}.{}", param.name);
+            self.add_label(&param_label, page);
+            self.define(&format!("{name}.{}", param.name), &param_label);
+            if anchor.is_none() {
+                self.add_anchor(page, &format!("parameters-{}", param.name), &param_label);
+            }
+        }
+
+        for scope in &func.scope {
+            let scope_name = format!("{name}.{}", scope.name);
+            let scope_anchor = format!("definitions-{}", scope.name);
+            self.add_func(scope, page, &label, &scope_name, Some(&scope_anchor));
+        }
+    }
+
+    /// Adds an item labelled as `{parent}.{item}` and defined as `name`.
+    fn add_item(&mut self, page: &str, parent: &str, item: &str, name: &str) -> String {
+        let label = format!("{parent}.{item}");
+        self.add_label(&label, page);
+        self.define(name, &label);
+        label
+    }
+
+    /// Registers the page a label is attached on.
+    fn add_label(&mut self, label: &str, page: &str) {
+        self.labels.insert(label.to_owned(), page.to_owned());
+    }
+
+    /// Registers the label of an anchor on a page, keeping the first one.
+    /// Labels other than the page are linked to their first anchor.
+    fn add_anchor(&mut self, page: &str, anchor: &str, label: &str) {
+        let Entry::Vacant(entry) = self.anchors.entry(format!("{page}#{anchor}")) else {
+            return;
+        };
+        entry.insert(label.to_owned());
+        if label != page {
+            self.label_anchors
+                .entry(label.to_owned())
+                .or_insert_with(|| anchor.to_owned());
+        }
+    }
+
+    /// Registers the label of an item, keeping the first definition.
+    fn define(&mut self, name: &str, label: &str) {
+        self.definitions
+            .entry(name.to_owned())
+            .or_insert_with(|| label.to_owned());
+    }
+
+    /// Resolves a link found on the page `page` to a label. Returns `None` if
+    /// the link points outside of the documentation.
+    pub fn resolve(&self, link: &str, page: &str) -> StrResult<Option<String>> {
+        if let Some(anchor) = link.strip_prefix('#') {
+            // Glossary links are kept as they are.
+            if anchor.starts_with("x-term-") {
+                return Ok(None);
+            }
+
+            return self.anchor(page, anchor).map(Some);
+        }
+
+        let Some(link) = link.strip_prefix('$') else {
+            return Ok(None);
+        };
+
+        let (path, anchor) = link.split_once('#').unwrap_or((link, ""));
+        let path = path.trim_end_matches('/');
+        let (head, tail) = path.split_once('/').unwrap_or((path, ""));
+
+        let route = match head {
+            "tutorial" => "tutorial",
+            "reference" | "category" => "reference",
+            "syntax" => "reference/syntax",
+            "styling" => "reference/styling",
+            "scripting" => "reference/scripting",
+            "context" => "reference/context",
+            "guides" => "guides",
+            "changelog" => "changelog",
+            "universe" => return Ok(None),
+            _ => {
+                if !tail.is_empty() {
+                    return Err(eco_format!("unexpected path after definition: {tail}"));
+                }
+
+                let label = self
+                    .definition(head)
+                    .ok_or_else(|| eco_format!("unknown definition: {head}"))?;
+                if anchor.is_empty() {
+                    return Ok(Some(label.to_owned()));
+                }
+
+                let page = self
+                    .labels
+                    .get(label)
+                    .ok_or_else(|| eco_format!("definition without page: {head}"))?;
+                return self.anchor(page, anchor).map(Some);
+            }
+        };
+
+        let route = if tail.is_empty() {
+            format!("{}{route}", self.base)
+        } else {
+            format!("{}{route}/{tail}", self.base)
+        };
+
+        let k = page_key(&route);
+        if !self.labels.contains_key(&k) {
+            return Err(eco_format!("unknown page: {route}"));
+        }
+
+        if anchor.is_empty() {
+            Ok(Some(k))
+        } else {
+            self.anchor(&k, anchor).map(Some)
+        }
+    }
+
+    /// Resolves the `index`-th of the `count` headings of the Markdown body
+    /// of a page to its label. A heading is matched by its ID, or by its
+    /// position if the ID is unknown, e.g. because the heading text was
+    /// translated, and the body has as many headings as the outline. Headings
+    /// of pages without a Markdown body are not labelled.
+    pub fn heading(
+        &self,
+        page: &str,
+        index: usize,
+        count: usize,
+        id: &str,
+    ) -> StrResult<Option<String>> {
+        let Some(ids) = self.outlines.get(page) else {
+            return Ok(None);
+        };
+
+        if ids.iter().any(|known| known == id) {
+            return Ok(Some(format!("{page}.{id}")));
+        }
+        if count != ids.len() {
+            return Err(eco_format!(
+                "unknown heading, and the outline of {page} has {} headings instead of {count}",
+                ids.len()
+            ));
+        }
+        Ok(Some(format!("{page}.{}", ids[index])))
+    }
+
+    /// Finds the label of an anchor on a page.
+    fn anchor(&self, page: &str, anchor: &str) -> StrResult<String> {
+        self.anchors
+            .get(&format!("{page}#{anchor}"))
+            .cloned()
+            .ok_or_else(|| eco_format!("unknown anchor: #{anchor} on {page}"))
+    }
+
+    /// Finds the label of an item or its closest parent, e.g. the label of
+    /// `array` for `array.foo`.
+    fn definition(&self, name: &str) -> Option<&str> {
+        let mut name = name;
+        loop {
+            if let Some(label) = self.definitions.get(name) {
+                return Some(label);
+            }
+            name = name.rsplit_once('.')?.0;
+        }
+    }
+}
+
+/// Gets the dotted name of a function including its module path, e.g.
+/// `math.frac`.
+fn qualified_name(func: &FuncMdModel) -> String {
+    func.path
+        .iter()
+        .chain(std::iter::once(&func.name))
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes a page with the given route and body.
+    fn page(route: &str, body: BodyMdModel, children: Vec<PageMdModel>) -> PageMdModel {
+        PageMdModel {
+            route: route.into(),
+            title: "".into(),
+            description: "".into(),
+            part: None,
+            outline: vec![],
+            body,
+            children,
+        }
+    }
+
+    /// Makes an outline item with the given ID.
+    fn outline(id: &str, children: Vec<OutlineMdItem>) -> OutlineMdItem {
+        OutlineMdItem {
+            id: id.into(),
+            name: "".into(),
+            children,
+        }
+    }
+
+    /// Makes a group page body.
+    fn group(name: &str, functions: &[&str]) -> BodyMdModel {
+        BodyMdModel::Group(GroupMdModel {
+            name: name.into(),
+            title: "".into(),
+            details: HtmlMd::Md("".into()),
+            functions: functions
+                .iter()
+                .map(|name| FuncMdModel {
+                    path: vec![],
+                    name: (*name).into(),
+                    title: "".into(),
+                    keywords: vec![],
+                    oneliner: "".into(),
+                    element: false,
+                    contextual: false,
+                    deprecation: None,
+                    details: HtmlMd::Md("".into()),
+                    example: None,
+                    self_: false,
+                    params: vec![],
+                    returns: vec![],
+                    scope: vec![],
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn resolves_links_against_page_tree() {
+        let html = || BodyMdModel::Html(HtmlMd::Md("".into()));
+        let pages = vec![
+            page("/", html(), vec![]),
+            PageMdModel {
+                outline: vec![outline("list-of-guides", vec![])],
+                ..page(
+                    "/guides/",
+                    html(),
+                    vec![PageMdModel {
+                        outline: vec![outline("basics", vec![outline("tables", vec![])])],
+                        ..page("/guides/table-guide/", html(), vec![])
+                    }],
+                )
+            },
+            page(
+                "/reference/",
+                html(),
+                vec![page(
+                    "/reference/foundations/calc/",
+                    group("calc", &["max"]),
+                    vec![],
+                )],
+            ),
+        ];
+        let index = LinkIndex::new(&pages);
+
+        let resolve = |link: &str| index.resolve(link, "guides");
+        assert_eq!(
+            resolve("$guides/table-guide"),
+            Ok(Some("guides.table-guide".into()))
+        );
+        assert_eq!(
+            resolve("$guides/table-guide/#tables"),
+            Ok(Some("guides.table-guide.tables".into()))
+        );
+        assert_eq!(
+            resolve("$guides/table-guide/#missing"),
+            Err("unknown anchor: #missing on guides.table-guide".into())
+        );
+        assert_eq!(
+            resolve("$calc.max"),
+            Ok(Some("reference.foundations.calc.calc.max".into()))
+        );
+        assert_eq!(
+            resolve("$calc.min"),
+            Ok(Some("reference.foundations.calc.calc".into()))
+        );
+        assert_eq!(
+            resolve("$calc/#functions-max"),
+            Ok(Some("reference.foundations.calc.calc.max".into()))
+        );
+        assert_eq!(
+            resolve("$category/foundations/calc"),
+            Ok(Some("reference.foundations.calc".into()))
+        );
+        assert_eq!(
+            resolve("#list-of-guides"),
+            Ok(Some("guides.list-of-guides".into()))
+        );
+        assert_eq!(
+            resolve("$calc/#functions-min"),
+            Err("unknown anchor: #functions-min on reference.foundations.calc".into())
+        );
+        assert_eq!(resolve("https://typst.app"), Ok(None));
+        assert_eq!(resolve("$universe"), Ok(None));
+        assert_eq!(
+            resolve("$guides/missing"),
+            Err("unknown page: /guides/missing".into())
+        );
+        assert_eq!(
+            resolve("$missing.item"),
+            Err("unknown definition: missing.item".into())
+        );
+        assert_eq!(
+            index.page_of("reference.foundations.calc.calc.max"),
+            Some("reference.foundations.calc")
+        );
+    }
+
+    #[test]
+    fn rewrites_links_with_map() {
+        let map: LinkMap = toml::from_str(
+            r#"
+            [links]
+            "https://typst.app/" = "https://example.com/typst/"
+            "https://typst.app/docs/" = "https://typst-doc-cn.github.io/docs/"
+            "$universe" = "https://typst.app/universe"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            map.rewrite("https://typst.app/docs/reference/"),
+            Some("https://typst-doc-cn.github.io/docs/reference/".into())
+        );
+        assert_eq!(
+            map.rewrite("https://typst.app/home"),
+            Some("https://example.com/typst/home".into())
+        );
+        assert_eq!(
+            map.rewrite("$universe/package/cetz"),
+            Some("https://typst.app/universe/package/cetz".into())
+        );
+        assert_eq!(map.rewrite("$text"), None);
+    }
+
+    #[test]
+    fn rewrites_links_to_excluded_pages() {
+        let html = || BodyMdModel::Html(HtmlMd::Md("".into()));
+        let mut pages = vec![
+            page("/", html(), vec![]),
+            page("/guides/", html(), vec![]),
+            page(
+                "/reference/",
+                html(),
+                vec![page(
+                    "/reference/foundations/calc/",
+                    group("calc", &["max"]),
+                    vec![],
+                )],
+            ),
+        ];
+        let map: LinkMap = toml::from_str(
+            r#"
+            [links]
+            "https://typst.app/docs/reference/" = "https://typst-doc-cn.github.io/docs/reference/"
+            "#,
+        )
+        .unwrap();
+        let index = LinkIndex::new(&pages).with_map(map);
+        pages.truncate(2);
+        let index = index.with_pages(&pages);
+
+        assert_eq!(index.rewrite("$guides"), None);
+        assert_eq!(index.rewrite("#anchor"), None);
+        assert_eq!(
+            index.rewrite("$calc.max"),
+            Some(
+                "https://typst-doc-cn.github.io/docs/reference/foundations/calc/#functions-max"
+                    .into()
+            )
+        );
+        assert_eq!(
+            index.rewrite("$reference"),
+            Some("https://typst-doc-cn.github.io/docs/reference/".into())
+        );
+        assert_eq!(index.resolve("$guides", "index"), Ok(Some("guides".into())));
+    }
+
+    #[test]
+    fn resolves_headings_against_outline() {
+        let html = BodyMdModel::Html(HtmlMd::Md("".into()));
+        let pages = vec![PageMdModel {
+            outline: vec![outline("basics", vec![outline("tables", vec![])])],
+            ..page("/guides/", html, vec![])
+        }];
+        let index = LinkIndex::new(&pages);
+
+        let heading =
+            |index_: usize, count: usize, id: &str| index.heading("guides", index_, count, id);
+        assert_eq!(heading(1, 2, "tables"), Ok(Some("guides.tables".into())));
+        assert_eq!(heading(0, 2, "基础"), Ok(Some("guides.basics".into())));
+        assert_eq!(heading(0, 3, "basics"), Ok(Some("guides.basics".into())));
+        assert_eq!(
+            heading(0, 3, "基础"),
+            Err("unknown heading, and the outline of guides has 2 headings instead of 3".into())
+        );
+        assert_eq!(
+            heading(2, 3, "extra"),
+            Err("unknown heading, and the outline of guides has 2 headings instead of 3".into())
+        );
+        assert_eq!(index.heading("reference", 0, 1, "basics"), Ok(None));
+    }
+}
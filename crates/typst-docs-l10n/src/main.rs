@@ -14,9 +14,15 @@ use tinymist_l10n::{
     TranslationMap, TranslationMapSet,
 };
 use typst_docs::provide;
+use typst_docs_l10n::assets::BookAssets;
+use typst_docs_l10n::commits::{AuthorMap, CommitLog};
+use typst_docs_l10n::convert::typst_str;
+use typst_docs_l10n::diag::Diagnostic;
+use typst_docs_l10n::filter::PageFilter;
 use typst_docs_l10n::generate::GenContext;
-use typst_docs_l10n::markdown::split_markdown;
-use typst_docs_l10n::resolve::CliResolver;
+use typst_docs_l10n::link::{LinkIndex, LinkMap};
+use typst_docs_l10n::markdown::{split_markdown, MARKDOWN_PAR_SEP};
+use typst_docs_l10n::resolve::{CliResolver, ExampleFormat};
 use typst_docs_l10n::translate::check_page;
 use typst_docs_l10n::PageMdModel;
 
@@ -80,6 +86,33 @@ struct GenerateArgs {
     #[arg(long, default_value = "/")]
     base: String,
 
+    /// The format of the example images. `svg` stays sharp at any zoom, and
+    /// `both` also renders PNG images as a fallback for the web viewer. All
+    /// pages of multi-page examples are rendered.
+    #[arg(long, value_enum, default_value_t = ExampleFormat::Png)]
+    example_format: ExampleFormat,
+
+    /// The resolution of the PNG example images, in dots per inch.
+    #[arg(long, default_value_t = CliResolver::DEFAULT_DPI)]
+    example_dpi: f32,
+
+    /// The commits of the Typst repository, passed to the resolver of
+    /// `typst-docs`. Either a local clone or a JSON snapshot mapping ranges
+    /// like `v0.12.0..v0.13.0` to commits in the format of the GitHub API.
+    /// The `<contributors>` tags of the changelog are kept in the output and
+    /// listed by the `make` command.
+    #[arg(long)]
+    commits: Option<PathBuf>,
+
+    /// A TOML file rewriting links in the documentation, e.g. to a localized
+    /// mirror. See the README for the format.
+    #[arg(long)]
+    link_map: Option<PathBuf>,
+
+    /// The pages written to the JSON output.
+    #[command(flatten)]
+    filter: PageFilter,
+
     /// Enable verbose logging. This will print out all the calls to the
     /// resolver and the paths of the generated assets.
     #[arg(long)]
@@ -93,18 +126,28 @@ fn generate(args: GenerateArgs) -> anyhow::Result<()> {
         base.push('/');
     }
 
-    let resolver = CliResolver {
-        assets_dir: &args.assets_dir,
-        verbose: args.verbose,
-        base: &base,
-    };
+    let mut resolver =
+        CliResolver::new(&args.assets_dir, &base, args.verbose).with_context(|| {
+            format!(
+                "Failed to load the example cache in {}",
+                args.assets_dir.display()
+            )
+        })?;
+    resolver.example_format = args.example_format;
+    resolver.example_dpi = args.example_dpi;
+    resolver.commits = args.commits.as_deref().map(CommitLog::open).transpose()?;
+    if let Some(path) = &args.link_map {
+        resolver.links = LinkMap::load(path)?;
+    }
     if args.verbose {
         eprintln!("resolver: {resolver:?}");
     }
-    let pages = provide(&resolver)
+    let mut pages = provide(&resolver)
         .into_iter()
         .map(|page| page.into())
         .collect::<Vec<PageMdModel>>();
+    args.filter.apply(&mut pages);
+    resolver.finish()?;
 
     eprintln!("Be warned: the JSON structure is not stable and may change at any time.");
     let json = serde_json::to_string_pretty(&pages)?;
@@ -128,14 +171,18 @@ struct TranslateArgs {
     /// The directory for the translated documentation.
     #[arg(long, default_value = "locales/docs")]
     translation_dir: PathBuf,
-}
 
-const MARKDOWN_PAR_SEP: &str = "\n\n";
+    /// The pages to translate. The translations of the other pages are
+    /// removed from the translation files.
+    #[command(flatten)]
+    filter: PageFilter,
+}
 
 /// Updates the translations of the documentation.
 fn translate(args: TranslateArgs) -> anyhow::Result<()> {
     let json = fs::read_to_string(&args.docs_file)?;
-    let pages: Vec<PageMdModel> = serde_json::from_str(&json)?;
+    let mut pages: Vec<PageMdModel> = serde_json::from_str(&json)?;
+    args.filter.apply(&mut pages);
 
     let sub_docs = args.translation_dir.join("typst-docs");
     std::fs::create_dir_all(&sub_docs)
@@ -184,21 +231,24 @@ fn write_large_translate(sub_docs: &Path, k: &str, v: &mut String) {
     *v = serde_json::to_string(&format!("{{{{{}}}}}", rel_path.display())).unwrap();
 }
 
-/// Reads a large translation file.
-fn read_large_translation(path: &Path, lang: &str) -> anyhow::Result<Vec<String>> {
+/// Reads a large translation file, picking the first available language of
+/// `langs` for each paragraph.
+fn read_large_translation(path: &Path, langs: &[String]) -> anyhow::Result<Vec<String>> {
     let content = fs::read_to_string(path)?;
     let pars = toml::from_str::<LargeTranslationFile>(&content)?.main;
 
-    Ok(pars
-        .into_iter()
-        .map(|par| {
-            // zh or en
-            let Some(content) = par.get(lang) else {
-                return par.get("en").unwrap().to_string();
-            };
-            content.to_string()
+    pars.into_iter()
+        .enumerate()
+        .map(|(idx, par)| {
+            langs
+                .iter()
+                .map(String::as_str)
+                .chain(["en"])
+                .find_map(|lang| par.get(lang))
+                .cloned()
+                .with_context(|| format!("Missing content for paragraph {idx}"))
         })
-        .collect())
+        .collect()
 }
 
 /// Arguments to make a typst document.
@@ -215,65 +265,201 @@ struct MakeArgs {
     /// The output directory for the typst document.
     #[arg(long, short, default_value = "target/typst-docs")]
     output_dir: PathBuf,
+
+    /// The directory the `generate` command wrote the assets to. The example
+    /// images referenced by the documentation are copied from it into the
+    /// `assets` directory next to the typst document.
+    #[arg(long, default_value = "dist/assets")]
+    assets_dir: PathBuf,
+
+    /// The base URL the `generate` command was run with.
+    #[arg(long, default_value = "/")]
+    base: String,
+
+    /// The languages to make documents for. Each language produces a
+    /// `docs.{lang}.typ` in the output directory.
+    #[arg(long = "lang", default_value = "zh")]
+    langs: Vec<String>,
+
+    /// The languages to fall back to, in order, when a translation is missing
+    /// for the requested language. The English source is always used last.
+    #[arg(long = "fallback", default_value = "en")]
+    fallbacks: Vec<String>,
+
+    /// How to report the problems found in the translations. Problems are
+    /// collected over all pages, and the command fails at the end if any of
+    /// them is an error.
+    #[arg(long, value_enum, default_value_t = DiagnosticFormat::Human)]
+    diagnostic_format: DiagnosticFormat,
+
+    /// A TOML file rewriting links in the documentation, e.g. to a localized
+    /// mirror. Pass the same file as to the `generate` command.
+    #[arg(long)]
+    link_map: Option<PathBuf>,
+
+    /// The commits of the Typst repository, used to list the contributors in
+    /// the changelog. Either a local clone or a JSON snapshot, as passed to
+    /// the `generate` command. Without it, the `<contributors>` tags are
+    /// reported and left out.
+    #[arg(long)]
+    commits: Option<PathBuf>,
+
+    /// A TOML file mapping the names and email addresses of the authors in a
+    /// local clone to their GitHub logins. Authors committing with a GitHub
+    /// no-reply address are known without it. See the README for the format.
+    #[arg(long)]
+    commit_authors: Option<PathBuf>,
+
+    /// The pages of the typst document.
+    #[command(flatten)]
+    filter: PageFilter,
+}
+
+/// The formats to report diagnostics in.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum DiagnosticFormat {
+    /// Prints each diagnostic and a summary to standard error.
+    Human,
+    /// Prints the diagnostics as a JSON array to standard output.
+    Json,
+}
+
+/// A diagnostic found while making the document of a language.
+#[derive(Debug, serde::Serialize)]
+struct MakeDiagnostic {
+    /// The language of the document.
+    lang: String,
+    /// The diagnostic.
+    #[serde(flatten)]
+    diagnostic: Diagnostic,
 }
 
 /// Makes a typst document.
 fn make(args: MakeArgs) -> anyhow::Result<()> {
     let json = fs::read_to_string(&args.docs_file)?;
-    let pages: Vec<PageMdModel> = serde_json::from_str(&json)?;
+    let mut pages: Vec<PageMdModel> = serde_json::from_str(&json)?;
 
     let translations_path = args.translation_dir.join("typst-docs.toml");
     let translations_str = fs::read_to_string(&translations_path)?;
     let raw = load_translations(&translations_str)?;
 
+    let link_map = match &args.link_map {
+        Some(path) => LinkMap::load(path)?,
+        None => LinkMap::default(),
+    };
+    // Links are resolved against all pages, so that links to the pages left
+    // out by the filter point to the online documentation.
+    let links = LinkIndex::new(&pages).with_map(link_map);
+    args.filter.apply(&mut pages);
+    let links = links.with_pages(&pages);
+    let assets = BookAssets::new(&args.base, &args.assets_dir, &args.output_dir);
+    let authors = match &args.commit_authors {
+        Some(path) => AuthorMap::load(path)?,
+        None => AuthorMap::default(),
+    };
+    let commits = args.commits.as_deref().map(CommitLog::open).transpose()?;
+    let commits = commits.map(|log| log.with_authors(authors));
+
+    std::fs::create_dir_all(&args.output_dir)?;
+    let mut diagnostics = vec![];
+    for lang in &args.langs {
+        let langs = std::iter::once(lang)
+            .chain(args.fallbacks.iter().filter(|fallback| *fallback != lang))
+            .cloned()
+            .collect::<Vec<_>>();
+        let translations = load_book_translations(&raw, &args.translation_dir, &langs)?;
+
+        let mut ctx = GenContext::new(&translations, langs, &links, &assets);
+        if let Some(log) = &commits {
+            ctx = ctx.with_commits(log);
+        }
+        let typst_pages = ctx.generate_pages(&pages)?;
+
+        let mut result = format!("#let docs-lang = {}\n", typst_str(lang));
+        result.push_str(include_str!("template.typ"));
+        ctx.write_pages(&typst_pages, &mut result)?;
+
+        let output_path = args.output_dir.join(format!("docs.{lang}.typ"));
+        fs::write(&output_path, &*result)?;
+
+        diagnostics.extend(
+            ctx.take_diagnostics()
+                .into_iter()
+                .map(|diagnostic| MakeDiagnostic {
+                    lang: lang.clone(),
+                    diagnostic,
+                }),
+        );
+    }
+
+    report_diagnostics(&diagnostics, args.diagnostic_format)
+}
+
+/// Reports the diagnostics of the `make` command, failing if any of them is an
+/// error.
+fn report_diagnostics(
+    diagnostics: &[MakeDiagnostic],
+    format: DiagnosticFormat,
+) -> anyhow::Result<()> {
+    match format {
+        DiagnosticFormat::Human => {
+            for diag in diagnostics {
+                eprintln!("[{}] {}", diag.lang, diag.diagnostic);
+            }
+        }
+        DiagnosticFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(diagnostics)?);
+        }
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diag| diag.diagnostic.is_error())
+        .count();
+    let warnings = diagnostics.len() - errors;
+    if format == DiagnosticFormat::Human && !diagnostics.is_empty() {
+        eprintln!("{errors} error(s), {warnings} warning(s)");
+    }
+    if errors > 0 {
+        anyhow::bail!("failed to make the documents: {errors} error(s)");
+    }
+
+    Ok(())
+}
+
+/// Loads the translations for a book, resolving large translation files
+/// against the language chain `langs`.
+fn load_book_translations(
+    raw: &TranslationMapSet,
+    translation_dir: &Path,
+    langs: &[String],
+) -> anyhow::Result<TranslationMapSet> {
     // todo: key first
     let mut translations = TranslationMapSet::default();
     for (lang, value) in raw {
         for (key, value) in value {
             if lang == "en" && value.starts_with("{{") && value.ends_with("}}") {
-                let path = args.translation_dir.join(&value[2..value.len() - 2]);
-                let en_par = read_large_translation(&path, "en")
-                    .with_context(|| format!("Failed to read large translation file: {path:?}"))?
-                    .join(MARKDOWN_PAR_SEP);
-                let zh_par = read_large_translation(&path, "zh")
+                let path = translation_dir.join(&value[2..value.len() - 2]);
+                let par = read_large_translation(&path, langs)
                     .with_context(|| format!("Failed to read large translation file: {path:?}"))?
                     .join(MARKDOWN_PAR_SEP);
 
                 translations
                     .entry(key.clone())
                     .or_default()
-                    .insert("en".to_owned(), en_par);
-                translations
-                    .entry(key)
-                    .or_default()
-                    .insert("zh".to_owned(), zh_par);
+                    .insert(langs[0].clone(), par);
                 continue;
             }
 
             translations
-                .entry(key)
+                .entry(key.clone())
                 .or_default()
-                .insert(lang.clone(), value);
+                .entry(lang.clone())
+                .or_insert_with(|| value.clone());
         }
     }
 
-    let mut ctx = GenContext::new(&translations);
-    let typst_pages = pages
-        .into_iter()
-        .flat_map(|page| ctx.generate_page(&page).transpose())
-        .collect::<anyhow::Result<Vec<_>>>()?;
-
-    let mut result = include_str!("template.typ").to_string();
-    for page in typst_pages {
-        let page = ctx.get_page(page);
-        page.write(&ctx, &mut result)?;
-    }
-
-    std::fs::create_dir_all(&args.output_dir)?;
-    let output_path = args.output_dir.join("docs.zh.typ");
-    fs::write(&output_path, &*result)?;
-
-    Ok(())
+    Ok(translations)
 }
 
 /// Arguments to save changes.
@@ -284,6 +470,7 @@ struct SaveArgs {
     translation_dir: PathBuf,
 }
 
+/// Saves the translations to disk.
 fn save(args: SaveArgs) -> anyhow::Result<()> {
     let input = std::io::stdin();
     let mut input = input.lock();
@@ -374,6 +561,7 @@ struct LargeTranslationFile {
     main: Vec<TranslationMap>,
 }
 
+/// Initializes a large translation file with the English paragraphs.
 fn init_large_translation(path: &Path, pars: &[&str]) -> anyhow::Result<()> {
     let mut file = fs::File::create(path)?;
     for par in pars.iter() {
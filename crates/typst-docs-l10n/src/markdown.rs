@@ -1,7 +1,7 @@
 //! Markdown helpers shared by localization tools.
 
 /// Separator used between Markdown paragraphs in translation files.
-const MARKDOWN_PAR_SEP: &str = "\n\n";
+pub const MARKDOWN_PAR_SEP: &str = "\n\n";
 
 /// Splits a markdown string into paragraphs while keeping fenced code blocks
 /// intact.
@@ -3,12 +3,44 @@
 //! may change at any time.
 
 use std::fs;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
+use serde::{Deserialize, Serialize};
 use typst::layout::PagedDocument;
 use typst_docs::{Html, Resolver};
 use typst_render::render;
 
+use crate::cache::{ExampleCache, ManifestEntry};
+use crate::commits::CommitLog;
+use crate::html::html_text;
+use crate::link::LinkMap;
+
+/// The formats the examples are rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExampleFormat {
+    /// Raster images, sharp at the configured resolution.
+    #[default]
+    Png,
+    /// Vector images, sharp at any resolution.
+    Svg,
+    /// Vector images, with raster images as a fallback.
+    Both,
+}
+
+impl ExampleFormat {
+    /// The extensions of the images of a page, in the order of preference.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Png => &["png"],
+            Self::Svg => &["svg"],
+            Self::Both => &["svg", "png"],
+        }
+    }
+}
+
 /// A resolver that uses the command line arguments to generate the
 /// documentation.
 #[derive(Debug)]
@@ -19,6 +51,173 @@ pub struct CliResolver<'a> {
     pub verbose: bool,
     /// The base URL for the documentation.
     pub base: &'a str,
+    /// The formats the examples are rendered in.
+    pub example_format: ExampleFormat,
+    /// The resolution of the raster images of the examples.
+    pub example_dpi: f32,
+    /// The commits listing the contributors of the changelog, if any.
+    pub commits: Option<CommitLog>,
+    /// The links rewritten in the documentation.
+    pub links: LinkMap,
+    /// The examples rendered by this run and the previous ones.
+    examples: ExampleCache,
+    /// The errors of writing the assets.
+    errors: Mutex<Vec<String>>,
+}
+
+impl<'a> CliResolver<'a> {
+    /// The default resolution of the raster images of the examples, i.e.
+    /// twice the resolution of the page.
+    pub const DEFAULT_DPI: f32 = 144.0;
+
+    /// Creates a resolver writing the assets to `assets_dir`, reusing the
+    /// examples rendered there by previous runs. The examples are rendered
+    /// to PNG at [`Self::DEFAULT_DPI`].
+    pub fn new(assets_dir: &'a Path, base: &'a str, verbose: bool) -> io::Result<Self> {
+        Ok(Self {
+            assets_dir,
+            verbose,
+            base,
+            example_format: ExampleFormat::default(),
+            example_dpi: Self::DEFAULT_DPI,
+            commits: None,
+            links: LinkMap::default(),
+            examples: ExampleCache::load(assets_dir)?,
+            errors: Mutex::default(),
+        })
+    }
+
+    /// Writes the manifest of the examples and removes the images of the
+    /// examples no longer in the documentation. Fails if any asset could not
+    /// be written.
+    pub fn finish(self) -> anyhow::Result<()> {
+        let errors = self.errors.into_inner().unwrap();
+        if !errors.is_empty() {
+            anyhow::bail!("failed to write assets: {}", errors.join(", "));
+        }
+
+        for path in self.examples.finish()? {
+            if self.verbose {
+                eprintln!("Removed unused example image {path:?}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the URL of an asset.
+    fn url(&self, filename: &str) -> String {
+        format!("{}assets/{filename}", self.base)
+    }
+
+    /// Gets the resolution of the raster images, if any.
+    fn dpi(&self) -> Option<f32> {
+        self.example_format
+            .extensions()
+            .contains(&"png")
+            .then_some(self.example_dpi)
+    }
+
+    /// Gets the file names of the images of an example with `pages` pages,
+    /// grouped by page.
+    fn example_files(&self, hash: u128, pages: usize) -> Vec<String> {
+        let key = ExampleCache::key(hash);
+        let mut files = vec![];
+        for page in 1..=pages {
+            for ext in self.example_format.extensions() {
+                files.push(match pages {
+                    1 => format!("{key}.{ext}"),
+                    _ => format!("{key}-{page}.{ext}"),
+                });
+            }
+        }
+        files
+    }
+
+    /// Renders all pages of an example into images, unless they were already
+    /// rendered. Returns the file names of the images, grouped by page.
+    fn render_example(
+        &self,
+        hash: u128,
+        source: Option<&Html>,
+        document: &PagedDocument,
+    ) -> io::Result<Vec<String>> {
+        let format = self.example_format;
+        let code = source.map(|code| ExampleCache::code_key(&html_text(code.as_str())));
+        if let Some(files) = self.examples.get(hash, format, self.dpi(), code.clone()) {
+            if self.verbose {
+                eprintln!("Reused example images {files:?}");
+            }
+            return Ok(files);
+        }
+
+        let files = self.example_files(hash, document.pages.len());
+        fs::create_dir_all(self.assets_dir)?;
+        let per_page = format.extensions().len();
+        for (page, names) in document.pages.iter().zip(files.chunks(per_page)) {
+            for filename in names {
+                let path = self.assets_dir.join(filename);
+                if filename.ends_with(".svg") {
+                    fs::write(&path, typst_svg::svg(page))?;
+                } else {
+                    render(page, self.example_dpi / 72.0)
+                        .save_png(path.as_path())
+                        .map_err(|err| io::Error::other(format!("{path:?}: {err}")))?;
+                }
+                eprintln!("Generated example image {path:?}");
+            }
+        }
+
+        self.examples.insert(
+            hash,
+            ManifestEntry {
+                files: files.clone(),
+                format,
+                dpi: self.dpi(),
+                code,
+            },
+        );
+        Ok(files)
+    }
+
+    /// Gets the HTML of the images of an example, one per page.
+    fn preview(&self, files: &[String]) -> String {
+        let per_page = self.example_format.extensions().len();
+        let pages = files.len() / per_page;
+        let mut html = String::new();
+        for (i, names) in files.chunks(per_page).enumerate() {
+            let alt = match pages {
+                1 => "Preview".to_owned(),
+                _ => format!("Preview (page {})", i + 1),
+            };
+            match names {
+                [svg, png] => html.push_str(&format!(
+                    r#"<picture><source srcset="{}" type="image/svg+xml"><img src="{}" alt="{alt}"></picture>"#,
+                    self.url(svg),
+                    self.url(png),
+                )),
+                _ => html.push_str(&format!(
+                    r#"<img src="{}" alt="{alt}">"#,
+                    self.url(&names[0])
+                )),
+            }
+        }
+        html
+    }
+
+    /// Records an error of writing an asset.
+    fn report(&self, err: impl std::fmt::Display) {
+        self.errors.lock().unwrap().push(err.to_string());
+    }
+}
+
+/// Writes an asset to `assets_dir`, returning its path.
+pub fn write_asset(assets_dir: &Path, filename: &str, data: &[u8]) -> io::Result<PathBuf> {
+    let path = assets_dir.join(filename);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, data)?;
+    Ok(path)
 }
 
 impl Resolver for CliResolver<'_> {
@@ -26,7 +225,16 @@ impl Resolver for CliResolver<'_> {
         if self.verbose {
             eprintln!("commits({from}, {to})");
         }
-        vec![]
+
+        // The commits were asked for with `--commits`, so failures are
+        // reported even without `--verbose`.
+        let Some(log) = &self.commits else {
+            return vec![];
+        };
+        log.docs_commits(from, to).unwrap_or_else(|err| {
+            eprintln!("warning: commits({from}, {to}) failed: {err:#}");
+            vec![]
+        })
     }
 
     fn example(
@@ -42,24 +250,22 @@ impl Resolver for CliResolver<'_> {
             );
         }
 
-        let page = document.pages.first().expect("page 0");
-        let pixmap = render(page, 2.0);
-        let filename = format!("{hash:x}.png");
-        let path = self.assets_dir.join(&filename);
-        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
-        pixmap.save_png(path.as_path()).expect("save png");
-        let src = format!("{}assets/{filename}", self.base);
-        eprintln!("Generated example image {path:?}");
+        let files = match self.render_example(hash, source.as_ref(), document) {
+            Ok(files) => files,
+            Err(err) => {
+                self.report(format_args!("example 0x{hash:x} ({err})"));
+                self.example_files(hash, document.pages.len())
+            }
+        };
+        let preview = self.preview(&files);
 
         if let Some(code) = source {
             let code_safe = code.as_str();
             Html::new(format!(
-                r#"<div class="previewed-code"><pre>{code_safe}</pre><div class="preview"><img src="{src}" alt="Preview"></div></div>"#
+                r#"<div class="previewed-code"><pre>{code_safe}</pre><div class="preview">{preview}</div></div>"#
             ))
         } else {
-            Html::new(format!(
-                r#"<div class="preview"><img src="{src}" alt="Preview"></div>"#
-            ))
+            Html::new(format!(r#"<div class="preview">{preview}</div>"#))
         }
     }
 
@@ -68,19 +274,19 @@ impl Resolver for CliResolver<'_> {
             eprintln!("image({filename}, {} bytes)", data.len());
         }
 
-        let path = self.assets_dir.join(filename);
-        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
-        fs::write(&path, data).expect("write image");
-        eprintln!("Created {} byte image at {path:?}", data.len());
+        match write_asset(self.assets_dir, filename, data) {
+            Ok(path) => eprintln!("Created {} byte image at {path:?}", data.len()),
+            Err(err) => self.report(format_args!("{filename} ({err})")),
+        }
 
-        format!("{}assets/{filename}", self.base)
+        self.url(filename)
     }
 
     fn link(&self, link: &str) -> Option<String> {
         if self.verbose {
             eprintln!("link({link})");
         }
-        None
+        self.links.rewrite(link)
     }
 
     fn base(&self) -> &str {
@@ -1,3 +1,81 @@
+#let docs-locales = (
+  en: (
+    region: none,
+    title: "Typst Documentation",
+    terms: (
+      parameters: "Parameters",
+      constructor: "Constructor",
+      definitions: "Definitions",
+      strings: "Available strings",
+      default: "Default",
+      element: "Element",
+      contextual: "Contextual",
+      required: "Required",
+      positional: "Positional",
+      variadic: "Variadic",
+      settable: "Settable",
+      deprecated: "Deprecated",
+      markup-shorthands: "Shorthands in markup mode",
+      math-shorthands: "Shorthands in math mode",
+      symbol: "Symbol",
+      name: "Name",
+      shorthand: "Shorthand",
+      math-class: "Math class",
+    ),
+  ),
+  fr: (
+    region: "fr",
+    title: "Documentation de Typst",
+    terms: (
+      parameters: "Paramètres",
+      constructor: "Constructeur",
+      definitions: "Définitions",
+      strings: "Chaînes disponibles",
+      default: "Valeur par défaut",
+      element: "Élément",
+      contextual: "Contextuel",
+      required: "Requis",
+      positional: "Positionnel",
+      variadic: "Variadique",
+      settable: "Paramétrable",
+      deprecated: "Obsolète",
+      markup-shorthands: "Raccourcis en mode balisage",
+      math-shorthands: "Raccourcis en mode mathématique",
+      symbol: "Symbole",
+      name: "Nom",
+      shorthand: "Raccourci",
+      math-class: "Classe mathématique",
+    ),
+  ),
+  zh: (
+    region: "cn",
+    title: "Typst官方文档翻译",
+    terms: (
+      parameters: "参数",
+      constructor: "构造函数",
+      definitions: "定义",
+      strings: "可选字符串",
+      default: "默认值",
+      element: "元素函数",
+      contextual: "上下文函数",
+      required: "必需",
+      positional: "位置参数",
+      variadic: "可变参数",
+      settable: "可设置",
+      deprecated: "已弃用",
+      markup-shorthands: "标记模式简写",
+      math-shorthands: "数学模式简写",
+      symbol: "符号",
+      name: "名称",
+      shorthand: "简写",
+      math-class: "数学类别",
+    ),
+  ),
+)
+
+#let docs-locale = docs-locales.at(docs-lang, default: docs-locales.en)
+#let docs-terms = (..docs-locales.en.terms, ..docs-locale.terms)
+
 #set heading(numbering: "1.")
 #set page(numbering: "1")
 #set par(justify: true)
@@ -6,19 +84,227 @@
     "Libertinus Serif",
     "Source Han Serif SC",
   ),
-  lang: "zh",
-  region: "cn",
+  lang: docs-lang,
+  region: docs-locale.region,
+)
+
+#let docs-part-numbering(depth, ..nums) = {
+  let nums = nums.pos()
+  if nums.len() > depth {
+    nums = nums.slice(0, depth - 1) + nums.slice(depth)
+  }
+  numbering("1.", ..nums)
+}
+
+#let docs-part(key: none, depth: 1, body) = {
+  let it = heading(depth: depth, numbering: none, body)
+  set align(center + horizon)
+  set text(size: 2em)
+  if key != none [#it#label(key)] else { it }
+}
+
+#let docs-description(body) = block(
+  below: 1.2em,
+  text(style: "italic", fill: luma(80), body),
+)
+
+#let docs-type(name) = box(
+  inset: (x: 3pt),
+  outset: (y: 2pt),
+  radius: 2pt,
+  fill: luma(230),
+  raw(name),
+)
+
+#let docs-badges(kinds) = {
+  for kind in kinds {
+    box(
+      inset: (x: 4pt),
+      outset: (y: 2pt),
+      radius: 2pt,
+      stroke: 0.5pt + luma(160),
+      text(size: 0.8em, docs-terms.at(kind, default: kind)),
+    )
+    h(4pt)
+  }
+}
+
+#let docs-deprecation(body) = block(
+  width: 100%,
+  inset: 8pt,
+  radius: 4pt,
+  fill: rgb("#fff4e5"),
+  [*#docs-terms.deprecated:* #body],
+)
+
+#let docs-section(kind) = block(
+  above: 1.2em,
+  below: 0.8em,
+  text(weight: "bold", docs-terms.at(kind, default: kind)),
+)
+
+#let docs-signature(name, method: false, params: (), returns: ()) = block(
+  width: 100%,
+  inset: 8pt,
+  radius: 4pt,
+  fill: luma(245),
+  {
+    if method { raw("self.") }
+    raw(name)
+    raw("(")
+    for param in params {
+      linebreak()
+      h(1em)
+      if param.variadic { raw("..") }
+      if param.named { raw(param.name + ": ") }
+      param.types.map(docs-type).join(h(2pt))
+      raw(",")
+    }
+    if params.len() > 0 { linebreak() }
+    raw(")")
+    if returns.len() > 0 {
+      raw(" -> ")
+      returns.map(docs-type).join(h(2pt))
+    }
+  },
+)
+
+#let docs-param(name, key: none, depth: 3, types: (), badges: ()) = {
+  let it = heading(depth: depth, numbering: none, outlined: false, raw(name))
+  if key != none [#it#label(key)] else { it }
+  types.map(docs-type).join(h(2pt))
+  h(6pt)
+  docs-badges(badges)
+}
+
+#let docs-string(value) = block(above: 0.8em, below: 0.4em, raw(repr(value), lang: "typc"))
+
+#let docs-default(value) = block(
+  [#docs-terms.default: #raw(value, lang: "typc")],
+)
+
+#let docs-category-items(items) = table(
+  columns: (auto, 1fr),
+  stroke: none,
+  ..items
+    .map(item => {
+      let name = if item.code { raw(item.name) } else { item.name }
+      let name = if item.target != none { link(item.target, name) } else { name }
+      (name, item.oneliner)
+    })
+    .flatten(),
+)
+
+#let docs-info-box(body) = block(
+  width: 100%,
+  inset: 8pt,
+  radius: 4pt,
+  fill: rgb("#e8f1fd"),
+  stroke: (left: 2pt + rgb("#3f7fd6")),
+  body,
+)
+
+#let docs-example(..parts) = block(
+  width: 100%,
+  breakable: false,
+  grid(
+    columns: (1fr,) * parts.pos().len(),
+    column-gutter: 8pt,
+    align: horizon,
+    ..parts.pos(),
+  ),
+)
+
+#let docs-preview(body) = block(
+  inset: 8pt,
+  radius: 4pt,
+  stroke: luma(220),
+  {
+    set image(width: 100%)
+    body
+  },
+)
+
+#let docs-details(body) = block(
+  width: 100%,
+  inset: 8pt,
+  radius: 4pt,
+  stroke: luma(220),
+  body,
+)
+
+#let docs-summary(body) = block(strong(body))
+
+#let docs-contributors(body) = block(
+  width: 100%,
+  columns(3, gutter: 8pt, {
+    set text(size: 0.9em)
+    body
+  }),
+)
+
+#let docs-kbd(body) = box(
+  inset: (x: 3pt),
+  outset: (y: 2pt),
+  radius: 2pt,
+  stroke: luma(180),
+  fill: luma(245),
+  text(size: 0.9em, body),
+)
+
+#let docs-table-header(..cells) = table.header(
+  ..cells.pos().map(cell => table.cell(fill: luma(235), strong(cell))),
+)
+
+#let docs-html-table(header: (), ..rows) = {
+  let rows = rows.pos()
+  let columns = calc.max(1, ..(header + rows).map(row => row.len()))
+  table(
+    columns: columns,
+    ..if header.len() > 0 { (docs-table-header(..header.flatten()),) },
+    ..rows.flatten(),
+  )
+}
+
+#let docs-symbols(symbols) = table(
+  columns: (auto, 1fr, auto, auto),
+  align: horizon,
+  table.header(
+    ..("symbol", "name", "shorthand", "math-class").map(kind => strong(docs-terms.at(kind))),
+  ),
+  ..symbols
+    .map(sym => (
+      text(size: 1.4em, sym.glyph),
+      {
+        raw(sym.name)
+        if sym.alternates.len() > 0 {
+          linebreak()
+          text(size: 0.8em, sym.alternates.map(raw).join(", "))
+        }
+        if sym.deprecation != none {
+          linebreak()
+          text(size: 0.8em, fill: rgb("#b35900"), sym.deprecation)
+        }
+      },
+      {
+        if sym.markup != none { raw(sym.markup, lang: "typ") }
+        if sym.markup != none and sym.math != none { linebreak() }
+        if sym.math != none { raw(sym.math, lang: "typc") }
+      },
+      if sym.class != none { raw(sym.class) },
+    ))
+    .flatten(),
 )
 
 #align(center)[
   #set text(size: 36pt)
-  Typst官方文档翻译
+  #docs-locale.title
 ]
 
 
 #pagebreak()
 
-#outline()
+#outline(depth: 3)
 
 #pagebreak()
 
@@ -11,12 +11,7 @@ pub fn check_page(page: PageMdModel, translations: &mut TranslationPairs) {
         check_page(child, translations);
     }
 
-    if page.route.contains("changelog") {
-        return;
-    }
-
-    let k = to_dot_path(&page.route);
-    let k = if k.is_empty() { "index".to_owned() } else { k };
+    let k = page_key(&page.route);
 
     translations.push((format!("{k}.title"), page.title.into()));
     translations.push((format!("{k}.description"), page.description.into()));